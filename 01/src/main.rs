@@ -1,21 +1,37 @@
 //! Day 01: Secret Entrance
 
+use std::fmt;
+use utilities::trace;
+
 /// Struct representing the dial.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 struct Dial {
     /// Current number the dial is pointing to.
-    number: i32,
+    number: i64,
+    /// Number of positions on the dial. Numbers go 0..size.
+    size: i64,
     /// Count of times the dial pointed to zero.
-    zero_count: u32,
+    zero_count: u64,
     /// Count of times the dial clicked to zero.
-    zero_count2: u32,
+    zero_count2: u64,
 }
 
 impl Dial {
+    /// Size of the dial from the puzzle, 0..=99.
+    const DEFAULT_SIZE: i64 = 100;
+
     /// Make a new Dial from starting number.
-    pub fn new(number: i32) -> Self {
+    pub fn new(number: i64) -> Self {
+        Self::with_size(number, Self::DEFAULT_SIZE)
+    }
+
+    /// Make a new Dial from starting number with `size` positions. Panics if size
+    /// isn't positive.
+    pub fn with_size(number: i64, size: i64) -> Self {
+        assert!(size > 0, "Dial::with_size got a size that isn't positive.");
         Self {
-            number,
+            number: number.rem_euclid(size),
+            size,
             zero_count: 0,
             zero_count2: 0,
         }
@@ -23,71 +39,224 @@ impl Dial {
 
     /// Add a Rotation to the Dial.
     fn add_rotation(&mut self, rotation: Rotation) {
-        match rotation {
-            Rotation::Left(i) => self.number = self.number - i as i32,
-            Rotation::Right(i) => self.number = self.number + i as i32,
-        };
-
-        // Above 99, subtract 100's until below 100.
-        while self.number > 99 {
-            self.number = self.number - 100;
-        }
-
-        // Below zero, add 100's until positive.
-        while self.number < 0 {
-            self.number = self.number + 100;
-        }
+        let end = i128::from(self.number) + rotation.delta();
+        self.number = end.rem_euclid(i128::from(self.size)) as i64;
 
         // Add zero_count if we stopped at 0
         if self.number == 0 {
-            self.zero_count = self.zero_count + 1;
+            self.zero_count += 1;
         }
     }
 
-    /// Add a Rotation to the Dial. Increment zero_count2 when crossing 0.
-    fn add_rotation2(&mut self, rotation: Rotation) {
-        println!(
+    /// Add a Rotation to the Dial. Increment zero_count2 when crossing 0. Returns
+    /// an event describing the rotation for logging.
+    fn add_rotation2(&mut self, rotation: Rotation) -> RotationEvent {
+        trace!(
+            Trace,
             "Dial::add_rotation2: Dial starting at {}. zero_count2: {}. Rotation: {rotation:?}",
-            self.number, self.zero_count2
+            self.number,
+            self.zero_count2
         );
 
-        // If we start at zero, we don't count first click in left direction.
-        let mut starting_zero = self.number == 0;
+        // Work in i128 so even i64::MAX steps can't overflow.
+        let size = i128::from(self.size);
+        let start = i128::from(self.number);
+        let end = start + rotation.delta();
+
+        // Count the multiples of size the dial clicks on, not counting where it
+        // started. Going right that's (start, end]. Going left it's [end, start).
+        let zeros = if end >= start {
+            end.div_euclid(size) - start.div_euclid(size)
+        } else {
+            (start - 1).div_euclid(size) - (end - 1).div_euclid(size)
+        };
+
+        self.number = end.rem_euclid(size) as i64;
+        self.zero_count2 += zeros as u64;
+
+        trace!(
+            Trace,
+            "Dial::add_rotation AFTER: Dial at {}. zero_count2: {}.", self.number, self.zero_count2
+        );
+
+        RotationEvent {
+            start: start as i64,
+            rotation,
+            end: self.number,
+            crossings: zeros as u64,
+            landed_on_zero: self.number == 0,
+        }
+    }
+}
+
+/// Record of a single rotation of the dial. Used for debugging the zero crossing
+/// logic step by step.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct RotationEvent {
+    /// Number the dial pointed to before the rotation.
+    start: i64,
+    /// Rotation that was added.
+    rotation: Rotation,
+    /// Number the dial pointed to after the rotation.
+    end: i64,
+    /// Times the dial clicked to 0 during the rotation, including where it stopped.
+    crossings: u64,
+    /// The dial stopped at 0.
+    landed_on_zero: bool,
+}
+
+impl RotationEvent {
+    /// Header line for the CSV format.
+    const CSV_HEADER: &str = "start,rotation,end,crossings,landed_on_zero";
+
+    /// Format the event as a CSV row.
+    fn to_csv(self) -> String {
+        format!(
+            "{},{},{},{},{}",
+            self.start, self.rotation, self.end, self.crossings, self.landed_on_zero
+        )
+    }
 
-        match rotation {
-            Rotation::Left(i) => self.number = self.number - i as i32,
-            Rotation::Right(i) => self.number = self.number + i as i32,
+    /// Format the event as a JSON object.
+    fn to_json(self) -> String {
+        format!(
+            "{{\"start\":{},\"rotation\":\"{}\",\"end\":{},\"crossings\":{},\"landed_on_zero\":{}}}",
+            self.start, self.rotation, self.end, self.crossings, self.landed_on_zero
+        )
+    }
+}
+
+impl TryFrom<&str> for RotationEvent {
+    type Error = &'static str;
+
+    /// Tries to convert a CSV row to a RotationEvent.
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let fields: Vec<&str> = value.trim().split(',').collect();
+        let [start, rotation, end, crossings, landed_on_zero] = fields[..] else {
+            return Err("Wrong number of fields for a RotationEvent.");
         };
+        if !rotation.starts_with(['L', 'R']) || rotation[1..].parse::<i64>().is_err() {
+            return Err("Failed to parse rotation.");
+        }
+        Ok(Self {
+            start: start.parse().map_err(|_| "Failed to parse start.")?,
+            rotation: Rotation::from(rotation),
+            end: end.parse().map_err(|_| "Failed to parse end.")?,
+            crossings: crossings
+                .parse()
+                .map_err(|_| "Failed to parse crossings.")?,
+            landed_on_zero: landed_on_zero
+                .parse()
+                .map_err(|_| "Failed to parse landed_on_zero.")?,
+        })
+    }
+}
 
-        if self.number > 99 {
-            // Above 99, subtract 100's until below 100.
-            while self.number > 99 {
-                self.number = self.number - 100;
-                self.zero_count2 = self.zero_count2 + 1;
-            }
-        } else if self.number < 0 {
-            // Below zero, add 100's until positive.
-            while self.number < 0 {
-                self.number = self.number + 100;
-                if !starting_zero {
-                    self.zero_count2 = self.zero_count2 + 1;
-                } else {
-                    starting_zero = false;
-                }
-            }
-            if self.number == 0 {
-                // Add zero_count if we stopped at 0
-                self.zero_count2 = self.zero_count2 + 1;
+/// Log of every rotation added to a dial, in order.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+struct EventLog {
+    /// Number of positions on the dial the log was recorded with.
+    size: i64,
+    /// Event for each rotation.
+    events: Vec<RotationEvent>,
+}
+
+/// First event where a replayed implementation disagreed with the log.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct ReplayMismatch {
+    /// Index of the event in the log.
+    index: usize,
+    /// Event from the log.
+    expected: RotationEvent,
+    /// Event from the implementation being checked.
+    actual: RotationEvent,
+}
+
+impl EventLog {
+    /// Add the rotations to a new dial with add_rotation2 and record the events.
+    fn record(dial: Dial, rotations: &[Rotation]) -> Self {
+        let mut dial = dial;
+        Self {
+            size: dial.size,
+            events: rotations
+                .iter()
+                .map(|&rotation| dial.add_rotation2(rotation))
+                .collect(),
+        }
+    }
+
+    /// Dump the log as CSV, starting with a `size,<n>` line for the dial size
+    /// and then a header line.
+    fn to_csv(&self) -> String {
+        let mut ret = format!("size,{}\n{}", self.size, RotationEvent::CSV_HEADER);
+        for event in &self.events {
+            ret.push('\n');
+            ret.push_str(&event.to_csv());
+        }
+        ret.push('\n');
+        ret
+    }
+
+    /// Dump the log as JSON.
+    fn to_json(&self) -> String {
+        let events: Vec<String> = self.events.iter().map(|event| event.to_json()).collect();
+        format!(
+            "{{\"size\":{},\"events\":[{}]}}\n",
+            self.size,
+            events.join(",")
+        )
+    }
+
+    /// Read a log back from CSV made by to_csv. The header line is optional, and
+    /// logs without a size line are for a dial of the default size.
+    fn from_csv(string: &str) -> Result<Self, &'static str> {
+        if string.trim_start().starts_with('{') {
+            return Err("Only CSV event logs can be read back, not JSON.");
+        }
+        let mut lines = string
+            .lines()
+            .filter(|line| !line.trim().is_empty() && line.trim() != RotationEvent::CSV_HEADER)
+            .peekable();
+        let size = match lines
+            .peek()
+            .and_then(|line| line.trim().strip_prefix("size,"))
+        {
+            Some(size) => {
+                lines.next();
+                size.parse().map_err(|_| "Failed to parse size.")?
             }
-        } else if self.number == 0 {
-            // Add zero_count if we stopped at 0 without going over.
-            self.zero_count2 = self.zero_count2 + 1;
+            None => Dial::DEFAULT_SIZE,
+        };
+        if size <= 0 {
+            return Err("Event log size isn't positive.");
         }
+        let events = lines
+            .map(RotationEvent::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { size, events })
+    }
 
-        println!(
-            "Dial::add_rotation AFTER: Dial at {}. zero_count2: {}.",
-            self.number, self.zero_count2
-        );
+    /// Replay the rotations through another implementation of add_rotation2 and
+    /// check it agrees with the log at every step. Returns the first mismatch.
+    fn replay(
+        &self,
+        mut add_rotation: impl FnMut(&mut Dial, Rotation) -> RotationEvent,
+    ) -> Result<(), ReplayMismatch> {
+        let Some(first) = self.events.first() else {
+            return Ok(());
+        };
+        let mut dial = Dial::with_size(first.start, self.size);
+        for (index, &expected) in self.events.iter().enumerate() {
+            let actual = add_rotation(&mut dial, expected.rotation);
+            if actual != expected {
+                return Err(ReplayMismatch {
+                    index,
+                    expected,
+                    actual,
+                });
+            }
+        }
+        Ok(())
     }
 }
 
@@ -95,9 +264,29 @@ impl Dial {
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 enum Rotation {
     /// Rotation "Left". Will subtract from current location.
-    Left(i16),
+    Left(i64),
     /// Rotation "Right". Will add to current location.
-    Right(i16),
+    Right(i64),
+}
+
+impl Rotation {
+    /// Signed number of steps. Left is negative, Right is positive.
+    fn delta(&self) -> i128 {
+        match *self {
+            Rotation::Left(i) => -i128::from(i),
+            Rotation::Right(i) => i128::from(i),
+        }
+    }
+}
+
+impl fmt::Display for Rotation {
+    /// Format the rotation the same way as the input. (E.g. "L68")
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rotation::Left(i) => write!(f, "L{i}"),
+            Rotation::Right(i) => write!(f, "R{i}"),
+        }
+    }
 }
 
 impl From<&str> for Rotation {
@@ -106,11 +295,11 @@ impl From<&str> for Rotation {
         let (direction, steps) = value.split_at(1);
         match direction {
             "L" => Self::Left(
-                i16::from_str_radix(steps, 10)
+                i64::from_str_radix(steps, 10)
                     .expect(&format!("Left failed to convert {steps} to steps.")),
             ),
             "R" => Self::Right(
-                i16::from_str_radix(steps, 10)
+                i64::from_str_radix(steps, 10)
                     .expect(&format!("Right failed to convert {steps} to steps.")),
             ),
             _ => panic!("Rotation::from_str got unexpected direction: {}", direction),
@@ -129,7 +318,7 @@ fn parse_text(string: &String) -> Vec<Rotation> {
 }
 
 /// Count the number of times the dial points to 0.
-fn part1(file_name: &str) -> u32 {
+fn part1(file_name: &str) -> u64 {
     let file_contents = std::fs::read_to_string(file_name).expect("Couldn't open file");
     let rotations = parse_text(&file_contents);
     let mut dial = Dial::new(50);
@@ -140,7 +329,7 @@ fn part1(file_name: &str) -> u32 {
 }
 
 /// Count the number of times the dial "clicks" to 0.
-fn part2(file_name: &str) -> u32 {
+fn part2(file_name: &str) -> u64 {
     let file_contents = std::fs::read_to_string(file_name).expect("Couldn't open file");
     let rotations = parse_text(&file_contents);
     let mut dial = Dial::new(50);
@@ -150,8 +339,59 @@ fn part2(file_name: &str) -> u32 {
     dial.zero_count2
 }
 
+/// Run one of the event log commands. Returns the text to print.
+/// - `log <file> [csv|json] [--size n]`: dump the events from adding the file's
+///   rotations to a dial with `n` positions.
+/// - `replay <csv file>`: check add_rotation2 still agrees with a dumped log.
+fn run_log_command(args: &[String]) -> String {
+    let usage = "Usage: day01 [log <file> [csv|json] [--size n] | replay <csv file>]";
+    match args {
+        [command, file_name, rest @ ..] if command == "log" => {
+            let mut format = "csv";
+            let mut size = Dial::DEFAULT_SIZE;
+            let mut flags = rest.iter();
+            while let Some(flag) = flags.next() {
+                match flag.as_str() {
+                    "csv" | "json" => format = flag,
+                    "--size" => {
+                        size = flags
+                            .next()
+                            .and_then(|size| size.parse().ok())
+                            .filter(|&size| size > 0)
+                            .expect("--size needs a positive number.");
+                    }
+                    _ => panic!("{usage}"),
+                }
+            }
+            let file_contents = std::fs::read_to_string(file_name).expect("Couldn't open file");
+            let log = EventLog::record(Dial::with_size(50, size), &parse_text(&file_contents));
+            match format {
+                "json" => log.to_json(),
+                _ => log.to_csv(),
+            }
+        }
+        [command, file_name] if command == "replay" => {
+            let file_contents = std::fs::read_to_string(file_name).expect("Couldn't open file");
+            let log = EventLog::from_csv(&file_contents)
+                .unwrap_or_else(|error| panic!("Failed to parse event log: {error}"));
+            match log.replay(Dial::add_rotation2) {
+                Ok(()) => format!("All {} events match.\n", log.events.len()),
+                Err(mismatch) => format!("{mismatch:?}\n"),
+            }
+        }
+        _ => panic!("{usage}"),
+    }
+}
+
 /// Main function / code entry point.
 fn main() {
+    // Any arguments left after the --trace/-v flags are an event log command.
+    let args = utilities::trace::init_from_args(std::env::args().skip(1));
+    if !args.is_empty() {
+        print!("{}", run_log_command(&args));
+        return;
+    }
+
     println!(
         "Number of times dial stopped at 0 for example1: {}",
         part1("example1.txt")
@@ -305,6 +545,175 @@ mod tests {
         assert_eq!(dial.zero_count2, 0);
     }
 
+    /// Test rotations far bigger than the dial, and the old i16 limit.
+    #[test]
+    fn test_add_rotation_huge() {
+        let mut dial = Dial::new(50);
+        dial.add_rotation2(Rotation::from("R1000000000"));
+        assert_eq!(dial.number, 50);
+        assert_eq!(dial.zero_count2, 10000000);
+        dial.add_rotation2(Rotation::from("L1000000050"));
+        assert_eq!(dial.number, 0);
+        assert_eq!(dial.zero_count2, 20000001);
+        dial.add_rotation(Rotation::Right(i64::MAX));
+        assert_eq!(dial.number, i64::MAX % 100);
+        dial.add_rotation2(Rotation::Left(i64::MAX));
+        assert_eq!(dial.number, 0);
+    }
+
+    /// Test dials that aren't 0..=99. Checks every rotation against clicking one
+    /// step at a time.
+    #[test]
+    fn test_dial_sizes() {
+        for size in [1, 2, 7, 100] {
+            for start in 0..size {
+                for delta in -25..=25 {
+                    let rotation = if delta < 0 {
+                        Rotation::Left(-delta)
+                    } else {
+                        Rotation::Right(delta)
+                    };
+                    let mut dial = Dial::with_size(start, size);
+                    dial.add_rotation2(rotation);
+
+                    let mut number = start;
+                    let mut clicks = 0;
+                    for _ in 0..delta.abs() {
+                        number = (number + delta.signum()).rem_euclid(size);
+                        if number == 0 {
+                            clicks += 1;
+                        }
+                    }
+                    assert_eq!(dial.number, number, "{size} {start} {rotation:?}");
+                    assert_eq!(dial.zero_count2, clicks, "{size} {start} {rotation:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Dial::with_size got a size that isn't positive.")]
+    fn test_dial_bad_size() {
+        Dial::with_size(0, 0);
+    }
+
+    /// The rotations from the example, same as test_add_rotation2.
+    const EXAMPLE_ROTATIONS: [Rotation; 10] = [
+        Rotation::Left(68),
+        Rotation::Left(30),
+        Rotation::Right(48),
+        Rotation::Left(5),
+        Rotation::Right(60),
+        Rotation::Left(55),
+        Rotation::Left(1),
+        Rotation::Left(99),
+        Rotation::Right(14),
+        Rotation::Left(82),
+    ];
+
+    #[test]
+    fn test_event_log_record() {
+        let log = EventLog::record(Dial::new(50), &EXAMPLE_ROTATIONS);
+        assert_eq!(log.size, 100);
+        assert_eq!(
+            log.events[0],
+            RotationEvent {
+                start: 50,
+                rotation: Rotation::Left(68),
+                end: 82,
+                crossings: 1,
+                landed_on_zero: false,
+            }
+        );
+        assert_eq!(
+            log.events[2],
+            RotationEvent {
+                start: 52,
+                rotation: Rotation::Right(48),
+                end: 0,
+                crossings: 1,
+                landed_on_zero: true,
+            }
+        );
+        let crossings: u64 = log.events.iter().map(|event| event.crossings).sum();
+        assert_eq!(crossings, 6);
+    }
+
+    #[test]
+    fn test_event_log_csv() {
+        let log = EventLog::record(Dial::new(50), &EXAMPLE_ROTATIONS[..2]);
+        let csv = log.to_csv();
+        assert_eq!(
+            csv,
+            "size,100\nstart,rotation,end,crossings,landed_on_zero\n50,L68,82,1,false\n82,L30,52,0,false\n"
+        );
+        assert_eq!(EventLog::from_csv(&csv), Ok(log.clone()));
+        // Logs from before the size line was added are for the default size.
+        let old_csv = csv.strip_prefix("size,100\n").unwrap();
+        assert_eq!(EventLog::from_csv(old_csv), Ok(log));
+        assert!(EventLog::from_csv("50,L68,82,1").is_err());
+        assert!(EventLog::from_csv("50,X68,82,1,false").is_err());
+        assert!(EventLog::from_csv("size,0\n").is_err());
+        assert!(EventLog::from_csv("size,x\n").is_err());
+    }
+
+    /// A log from a dial that isn't the default size replays on the same size.
+    #[test]
+    fn test_event_log_csv_size() {
+        let log = EventLog::record(Dial::with_size(50, 60), &EXAMPLE_ROTATIONS);
+        let read_back = EventLog::from_csv(&log.to_csv()).unwrap();
+        assert_eq!(read_back.size, 60);
+        assert_eq!(read_back.replay(Dial::add_rotation2), Ok(()));
+    }
+
+    #[test]
+    fn test_event_log_json() {
+        let log = EventLog::record(Dial::new(50), &EXAMPLE_ROTATIONS[..1]);
+        assert_eq!(
+            log.to_json(),
+            "{\"size\":100,\"events\":[{\"start\":50,\"rotation\":\"L68\",\"end\":82,\"crossings\":1,\"landed_on_zero\":false}]}\n"
+        );
+        assert!(EventLog::from_csv(&log.to_json()).is_err());
+    }
+
+    #[test]
+    fn test_event_log_replay() {
+        let log = EventLog::record(Dial::new(50), &EXAMPLE_ROTATIONS);
+        assert_eq!(log.replay(Dial::add_rotation2), Ok(()));
+
+        // Clicking one step at a time should agree too.
+        let one_click_at_a_time = |dial: &mut Dial, rotation: Rotation| {
+            let start = dial.number;
+            let mut crossings = 0;
+            let delta = rotation.delta();
+            for _ in 0..delta.abs() {
+                dial.number = (dial.number + delta.signum() as i64).rem_euclid(dial.size);
+                if dial.number == 0 {
+                    crossings += 1;
+                }
+            }
+            RotationEvent {
+                start,
+                rotation,
+                end: dial.number,
+                crossings,
+                landed_on_zero: dial.number == 0,
+            }
+        };
+        assert_eq!(log.replay(one_click_at_a_time), Ok(()));
+
+        // Only counting where the dial stops misses the first crossing.
+        let stops_only = |dial: &mut Dial, rotation: Rotation| {
+            let mut event = dial.add_rotation2(rotation);
+            event.crossings = u64::from(event.landed_on_zero);
+            event
+        };
+        let mismatch = log.replay(stops_only).unwrap_err();
+        assert_eq!(mismatch.index, 0);
+        assert_eq!(mismatch.expected.crossings, 1);
+        assert_eq!(mismatch.actual.crossings, 0);
+    }
+
     /// Test against the example.
     #[test]
     fn part2_example01() {
@@ -1,5 +1,10 @@
 //! Day 02: Gift Shop
 
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+};
+
 /// Struct representing an ID range.
 #[derive(Debug, Eq, Hash, PartialEq)]
 struct ProductID {
@@ -7,182 +12,400 @@ struct ProductID {
     start: u64,
     /// End of the ID range.
     end: u64,
-    /// Invalid IDs.
-    invalid_ids: Vec<u64>,
-    /// Chunk sizes.
-    invalid_ids2: Vec<u64>,
 }
 
 impl ProductID {
     /// Make a new ProductID from two numbers.
     fn new(start: u64, end: u64) -> Self {
-        Self {
-            start,
-            end,
-            invalid_ids: Vec::new(),
-            invalid_ids2: Vec::new(),
-        }
+        Self { start, end }
+    }
+
+    /// Run through every ID in the range, giving the ones the rule says are
+    /// invalid.
+    fn scan_invalids<'a>(&self, rule: &'a Rule) -> impl Iterator<Item = u64> + 'a {
+        (self.start..=self.end).filter(|&id| rule.is_invalid(id))
     }
 
-    /// Sum the Invalid IDs for getting final answer.
-    fn sum_invalids(&self) -> u64 {
-        let mut sum: u64 = 0;
-        for id in &self.invalid_ids {
-            sum += id
+    /// Count and sum the invalid IDs for the rule. Repeat patterns are done with
+    /// arithmetic so no ID gets visited. Other patterns fall back to scanning.
+    fn invalid_totals(&self, rule: &Rule) -> (u128, u128) {
+        let blocks = match rule.pattern {
+            Pattern::ExactRepeats(_) | Pattern::MinRepeats(_) => rule.max_digits(),
+            // Fold instead of collecting so memory doesn't grow with the
+            // number of invalid IDs.
+            Pattern::Palindrome | Pattern::RotatedBlocks(_) => {
+                return self
+                    .scan_invalids(rule)
+                    .fold((0, 0), |(count, sum), id| (count + 1, sum + u128::from(id)));
+            }
+        };
+
+        let mut totals = (0, 0);
+        for digits in 1..=blocks {
+            for block in rule.pattern.block_sizes(digits) {
+                let (count, sum) = match rule.pattern {
+                    // Every ID with `block` digits repeated counts, even if a
+                    // shorter block also repeats. (1111 is 11 twice.)
+                    Pattern::ExactRepeats(_) => {
+                        block_totals(self.start, self.end, digits, block, rule.base)
+                    }
+                    // Every repeated ID has exactly one shortest block, so adding
+                    // up the primitive blocks never counts an ID twice.
+                    _ => primitive_block_totals(self.start, self.end, digits, block, rule.base),
+                };
+                totals.0 += count;
+                totals.1 += sum;
+            }
         }
-        sum
+        totals
     }
+}
+
+/// Pattern of digits that makes an ID invalid.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Pattern {
+    /// Digits are a block repeated exactly this many times.
+    ExactRepeats(u32),
+    /// Digits are a block repeated at least this many times.
+    MinRepeats(u32),
+    /// Digits read the same forwards and backwards.
+    Palindrome,
+    /// Digits split into this many blocks, each block being the previous block
+    /// rotated left by one digit. (E.g. 123231312 with 3 blocks.)
+    RotatedBlocks(u32),
+}
 
-    /// Sum the Invalid IDs for getting final answer.
-    fn sum_invalids2(&self) -> u64 {
-        let mut sum: u64 = 0;
-        for id in &self.invalid_ids2 {
-            sum += id
+impl Pattern {
+    /// Block sizes a number with `digits` digits can be split into for this
+    /// pattern. Empty for patterns that don't use blocks.
+    fn block_sizes(&self, digits: u32) -> Vec<u32> {
+        match *self {
+            Pattern::ExactRepeats(repeats) | Pattern::RotatedBlocks(repeats) => {
+                if repeats > 0 && digits.is_multiple_of(repeats) {
+                    vec![digits / repeats]
+                } else {
+                    vec![]
+                }
+            }
+            Pattern::MinRepeats(repeats) => (1..=digits)
+                .filter(|&block| digits.is_multiple_of(block) && digits / block >= repeats)
+                .collect(),
+            Pattern::Palindrome => vec![],
         }
-        sum
     }
+}
 
-    /// Adds the invalid id to the struct.
-    fn push_invalid(&mut self, number: u64) {
-        self.invalid_ids.push(number);
+impl TryFrom<&str> for Pattern {
+    type Error = &'static str;
+
+    /// Tries to convert &str like "exact:2", "min:2", "palindrome" or "rotated:3"
+    /// to a Pattern.
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if value == "palindrome" {
+            return Ok(Self::Palindrome);
+        }
+        let (name, repeats) = match value.split_once(':') {
+            None => return Err("Failed to split pattern on ':'"),
+            Some((name, repeats)) => (name, repeats.parse::<u32>()),
+        };
+        let repeats = match repeats {
+            Ok(r) => r,
+            Err(_) => return Err("Failed to parse pattern repeats."),
+        };
+        if repeats < 2 {
+            return Err("Pattern repeats must be at least 2.");
+        }
+        match name {
+            "exact" => Ok(Self::ExactRepeats(repeats)),
+            "min" => Ok(Self::MinRepeats(repeats)),
+            "rotated" => Ok(Self::RotatedBlocks(repeats)),
+            _ => Err("Unknown pattern name."),
+        }
     }
+}
 
-    /// Run through all the ProductIDs and add the invalids to the vec.
-    fn find_invalids(&mut self) {
-        for sequence in self.start..=self.end {
-            if check_invalid(sequence) {
-                self.push_invalid(sequence);
-            }
+/// Rule for deciding if an ID is invalid. The pattern is checked against the
+/// digits of the ID written in `base`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct Rule {
+    /// Pattern the digits have to match to be invalid.
+    pattern: Pattern,
+    /// Base the ID is written in. 2..=36.
+    base: u32,
+}
+
+impl Rule {
+    /// Rule for part 1: digits repeat exactly twice.
+    const PART1: Rule = Rule::new(Pattern::ExactRepeats(2), 10);
+
+    /// Rule for part 2: digits repeat at least twice.
+    const PART2: Rule = Rule::new(Pattern::MinRepeats(2), 10);
+
+    /// Make a new Rule. Panics if base isn't 2..=36, or if the pattern repeats
+    /// blocks fewer than 2 times, as then the whole number counts as a block.
+    const fn new(pattern: Pattern, base: u32) -> Self {
+        assert!(base >= 2 && base <= 36, "Rule::new got base out of range.");
+        if let Pattern::ExactRepeats(repeats)
+        | Pattern::MinRepeats(repeats)
+        | Pattern::RotatedBlocks(repeats) = pattern
+        {
+            assert!(repeats >= 2, "Rule::new got fewer than 2 repeats.");
         }
+        Self { pattern, base }
     }
 
-    /// Run through all the ProductIDs and add the invalids to the vec.
-    fn find_invalids2(&mut self) {
-        for sequence in self.start..=self.end {
-            // Once we confirm the number is invalid, skip checking other chunk_sizes.
-            let mut skip_ahead = false;
-            let chunk_sizes = get_chunk_sizes(sequence);
-            for chunk_size in chunk_sizes {
-                if skip_ahead {
-                    break;
-                };
-                let chunks = get_chunks(sequence, chunk_size);
-                if check_invalid2(&chunks) {
-                    self.invalid_ids2.push(sequence);
-                    skip_ahead = true;
-                }
-            }
+    /// Most digits a u64 can have in this base.
+    fn max_digits(&self) -> u32 {
+        u64::MAX.ilog(u64::from(self.base)) + 1
+    }
+
+    /// Checks if the number matches the pattern. Returns true if invalid.
+    fn is_invalid(&self, number: u64) -> bool {
+        let digits = get_digits(number, self.base);
+        match self.pattern {
+            Pattern::ExactRepeats(_) | Pattern::MinRepeats(_) => self
+                .pattern
+                .block_sizes(digits.len() as u32)
+                .into_iter()
+                .any(|block| blocks_match(&digits, block as usize)),
+            Pattern::Palindrome => digits.iter().eq(digits.iter().rev()),
+            Pattern::RotatedBlocks(_) => self
+                .pattern
+                .block_sizes(digits.len() as u32)
+                .into_iter()
+                .any(|block| blocks_rotate(&digits, block as usize)),
         }
     }
 }
 
-/// Check if number has even number of digits
-fn even_digits(number: u64) -> bool {
-    let num_digits = number.checked_ilog10().unwrap_or(0) + 1;
-    if num_digits.rem_euclid(2) == 0 {
-        true
-    } else {
-        false
+/// Get the digits of the number in the base, most significant first.
+fn get_digits(mut number: u64, base: u32) -> Vec<u8> {
+    let base = u64::from(base);
+    let mut ret = Vec::new();
+    loop {
+        ret.push((number % base) as u8);
+        number /= base;
+        if number == 0 {
+            break;
+        }
     }
+    ret.reverse();
+    ret
 }
 
-/// Checks if number is invalid by turning it into a vec of chars and splitting
-/// the vec in two. If the vecs match, it is invalid. Returns true if invalid.
-fn check_invalid(number: u64) -> bool {
-    if !even_digits(number) {
-        return false;
-    };
-    let mut characters1: Vec<char> = number.to_string().chars().collect();
-    let characters2 = characters1.split_off(characters1.len() / 2);
-    characters1 == characters2
+/// Checks the digits split into `block` sized chunks all match. Returns true if
+/// invalid.
+fn blocks_match(digits: &[u8], block: usize) -> bool {
+    digits.len() > block && digits.chunks(block).all(|chunk| chunk == &digits[..block])
 }
 
-/// Checks all the Vecs match. Returns true if invalid.
-fn check_invalid2(chunks: &Vec<Vec<char>>) -> bool {
-    for chunk in chunks {
-        if chunk != &chunks[0] {
-            return false;
-        }
+/// Checks each `block` sized chunk of the digits is the chunk before it rotated
+/// left by one digit. Returns true if invalid.
+fn blocks_rotate(digits: &[u8], block: usize) -> bool {
+    digits.len() > block
+        && digits
+            .chunks(block)
+            .zip(digits.chunks(block).skip(1))
+            .all(|(previous, next)| {
+                let mut rotated = previous.to_vec();
+                rotated.rotate_left(1);
+                rotated == next
+            })
+}
+
+/// Multiplier that repeats a block of `block` digits to fill `digits` digits.
+/// E.g. (6, 2) gives 10101 in base 10 since 12 * 10101 = 121212.
+fn repeat_multiplier(digits: u32, block: u32, base: u32) -> u128 {
+    let base = u128::from(base);
+    (base.pow(digits) - 1) / (base.pow(block) - 1)
+}
+
+/// Count and sum of the numbers in start..=end with `digits` digits that are a
+/// `block` digit number repeated. Numbers with a shorter repeating block that
+/// divides `block` are included too. (1111 is 11 repeated as well as 1.)
+fn block_totals(start: u64, end: u64, digits: u32, block: u32, base: u32) -> (u128, u128) {
+    // Clamp the range to numbers with the right number of digits.
+    let base_u128 = u128::from(base);
+    let low = u128::from(start).max(base_u128.pow(digits - 1));
+    let high = u128::from(end).min(base_u128.pow(digits) - 1);
+    if low > high {
+        return (0, 0);
+    }
+
+    // Every match is block_value * multiplier, so find the block_values in range.
+    let multiplier = repeat_multiplier(digits, block, base);
+    let block_low = low.div_ceil(multiplier).max(base_u128.pow(block - 1));
+    let block_high = (high / multiplier).min(base_u128.pow(block) - 1);
+    if block_low > block_high {
+        return (0, 0);
     }
-    true
+
+    let count = block_high - block_low + 1;
+    let sum = multiplier * (block_low + block_high) * count / 2;
+    (count, sum)
 }
 
-/// Figure out what chunks we can split the number's digits in to equally.
-fn get_chunk_sizes(number: u64) -> Vec<u32> {
-    // Get number of digits in the number.
-    let num_digits = number.checked_ilog10().unwrap_or(0) + 1;
-    // Get half the number of digits to figure out how far to iterate.
-    let half_digits = num_digits / 2;
-    // Figure out what sized chunks we can split into by checking remainders.
-    let mut chunks: Vec<u32> = Vec::new();
-    for chunk in 1..=half_digits {
-        if num_digits % chunk != 0 {
-            continue;
+/// Möbius function. 0 if n has a squared prime factor, otherwise -1 or 1 for an
+/// odd or even number of prime factors.
+fn mobius(mut n: u32) -> i32 {
+    let mut ret = 1;
+    let mut factor = 2;
+    while factor * factor <= n {
+        if n.is_multiple_of(factor) {
+            n /= factor;
+            if n.is_multiple_of(factor) {
+                return 0;
+            }
+            ret = -ret;
         }
-        chunks.push(chunk);
+        factor += 1;
     }
-    chunks
+    if n > 1 { -ret } else { ret }
 }
 
-/// Given the number and chunk size, split the number into chunks of chars.
-fn get_chunks(number: u64, chunk_size: u32) -> Vec<Vec<char>> {
-    let mut ret: Vec<Vec<char>> = Vec::new();
-    // Get initial Vec of chars from the number.
-    let mut characters: Vec<char> = number.to_string().chars().collect();
-    let number_of_chunks = characters.len() / chunk_size as usize;
-    let (mut left, mut right) = characters.split_at_mut(0);
-    // Left should be empty here. Just using it to initilize the variable. The assert
-    // gets rid of the warning.
-    assert_eq!(left, []);
-    for _ in 0..number_of_chunks {
-        (left, right) = right.split_at_mut(chunk_size as usize);
-        ret.push(left.to_vec());
+/// Count and sum of the numbers in start..=end with `digits` digits whose
+/// shortest repeating block is exactly `block` digits long. Uses inclusion-exclusion
+/// over the divisors of `block` to take out the numbers with shorter blocks.
+fn primitive_block_totals(
+    start: u64,
+    end: u64,
+    digits: u32,
+    block: u32,
+    base: u32,
+) -> (u128, u128) {
+    let mut count: i128 = 0;
+    let mut sum: i128 = 0;
+    for divisor in (1..=block).filter(|divisor| block.is_multiple_of(*divisor)) {
+        let sign = i128::from(mobius(block / divisor));
+        if sign == 0 {
+            continue;
+        }
+        let (divisor_count, divisor_sum) = block_totals(start, end, digits, divisor, base);
+        count += sign * divisor_count as i128;
+        sum += sign * divisor_sum as i128;
     }
-    ret
+    (count as u128, sum as u128)
 }
 
 /// Parse the incoming file to Vec of ProductID's
 fn parse_text(string: &String) -> Vec<ProductID> {
     let mut ret: Vec<ProductID> = Vec::new();
     for id_range in string.trim().split(',') {
-        let (start_raw, end_raw) = id_range.split_once('-').expect("Failed to split id_range.");
-        let start = u64::from_str_radix(start_raw, 10)
-            .expect(&format!("Failed to convert {start_raw} to u32."));
-        let end = u64::from_str_radix(end_raw, 10)
-            .expect(&format!("Failed to convert {end_raw} to u32."));
-        ret.push(ProductID::new(start, end));
+        ret.push(parse_range(id_range));
     }
     ret
 }
 
+/// Parse a single `start-end` range to a ProductID.
+fn parse_range(id_range: &str) -> ProductID {
+    let (start_raw, end_raw) = id_range.split_once('-').expect("Failed to split id_range.");
+    let start = u64::from_str_radix(start_raw, 10)
+        .expect(&format!("Failed to convert {start_raw} to u64."));
+    let end =
+        u64::from_str_radix(end_raw, 10).expect(&format!("Failed to convert {end_raw} to u64."));
+    ProductID::new(start, end)
+}
+
+/// Iterator over the ranges in a comma separated list that reads one range at a
+/// time, so the whole list never has to be in memory.
+struct RangeReader<R: BufRead> {
+    /// Where the ranges are read from.
+    reader: R,
+    /// Bytes of the range currently being read. Reused between ranges.
+    buffer: Vec<u8>,
+}
+
+impl<R: BufRead> RangeReader<R> {
+    /// Make a new RangeReader from anything buffered, like a file or stdin.
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for RangeReader<R> {
+    type Item = ProductID;
+
+    /// Read up to the next comma and parse it to a ProductID.
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buffer.clear();
+            let read = self
+                .reader
+                .read_until(b',', &mut self.buffer)
+                .expect("RangeReader failed to read.");
+            if read == 0 {
+                return None;
+            }
+            let id_range = std::str::from_utf8(&self.buffer)
+                .expect("RangeReader got invalid UTF-8.")
+                .trim_end_matches(',')
+                .trim();
+            // Skip empty entries like a trailing comma or newline.
+            if !id_range.is_empty() {
+                return Some(parse_range(id_range));
+            }
+        }
+    }
+}
+
+/// Count and sum the invalid IDs for the rule while streaming the ranges from the
+/// reader. Only the totals are kept, so memory stays constant no matter how many
+/// ranges there are.
+fn stream_totals(reader: impl BufRead, rule: &Rule) -> (u128, u128) {
+    RangeReader::new(reader)
+        .map(|id| id.invalid_totals(rule))
+        .fold((0, 0), |(count, sum), (id_count, id_sum)| {
+            (count + id_count, sum + id_sum)
+        })
+}
+
+/// Add all invalid IDs together for any rule.
+fn sum_invalids_by_rule(file_name: &str, rule: &Rule) -> u128 {
+    let file_contents = std::fs::read_to_string(file_name).expect("Couldn't open file");
+    let product_ids = parse_text(&file_contents);
+    product_ids.iter().map(|id| id.invalid_totals(rule).1).sum()
+}
+
 /// Add all invalid IDs together. Invalid IDs are sequences of digits that repeat
 /// twice.
 fn part1(file_name: &str) -> u64 {
-    let file_contents = std::fs::read_to_string(file_name).expect("Couldn't open file");
-    let mut product_ids = parse_text(&file_contents);
-    let mut sum = 0;
-    for id in product_ids.iter_mut() {
-        id.find_invalids();
-        sum += id.sum_invalids();
-    }
-    sum
+    let sum = sum_invalids_by_rule(file_name, &Rule::PART1);
+    u64::try_from(sum).expect("Sum of invalid IDs doesn't fit in u64.")
 }
 
 /// Add all invalid IDs together. Invalid IDs are sequences of digits that repeat
 /// at least twice.
 fn part2(file_name: &str) -> u64 {
-    let file_contents = std::fs::read_to_string(file_name).expect("Couldn't open file");
-    let mut product_ids = parse_text(&file_contents);
-    let mut sum = 0;
-    for id in product_ids.iter_mut() {
-        id.find_invalids2();
-        sum += id.sum_invalids2();
-    }
-    sum
+    let sum = sum_invalids_by_rule(file_name, &Rule::PART2);
+    u64::try_from(sum).expect("Sum of invalid IDs doesn't fit in u64.")
 }
 
 /// Main function / code entry point.
 fn main() {
+    // `day02 <file> <pattern> [base]` streams the ranges and sums the invalid IDs
+    // for any rule instead of printing the puzzle answers. A file of `-` reads
+    // from stdin.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let [file_name, pattern, rest @ ..] = args.as_slice() {
+        let pattern = Pattern::try_from(pattern.as_str()).expect("Failed to parse pattern.");
+        let base = rest
+            .first()
+            .map(|base| base.parse().expect("Failed to parse base."))
+            .unwrap_or(10);
+        let rule = Rule::new(pattern, base);
+        let (count, sum) = if file_name == "-" {
+            stream_totals(io::stdin().lock(), &rule)
+        } else {
+            let file = File::open(file_name).expect("Couldn't open file");
+            stream_totals(BufReader::new(file), &rule)
+        };
+        println!("Invalid IDs for {file_name} with {rule:?}: {count}. Sum: {sum}");
+        return;
+    }
+
     println!("Sum of invalid IDs for example1: {}", part1("example1.txt"));
     println!("Sum of invalid IDs for input: {}", part1("input.txt"));
     println!(
@@ -229,89 +452,307 @@ mod tests {
     }
 
     #[test]
-    fn test_sum_invalids() {
-        let mut id = ProductID::new(11, 22);
-        id.invalid_ids = vec![11, 22];
-        assert_eq!(id.sum_invalids(), 33);
+    fn test_range_reader() {
+        let input = "11-22,95-115,\n998-1012\n,,1-2\n".as_bytes();
+        let expected = vec![
+            ProductID::new(11, 22),
+            ProductID::new(95, 115),
+            ProductID::new(998, 1012),
+            ProductID::new(1, 2),
+        ];
+        assert_eq!(RangeReader::new(input).collect::<Vec<_>>(), expected);
+        assert_eq!(RangeReader::new("".as_bytes()).count(), 0);
     }
 
     #[test]
-    fn test_even_digits() {
-        assert_eq!(even_digits(5), false);
-        assert_eq!(even_digits(10), true);
-        assert_eq!(even_digits(101), false);
+    fn test_stream_totals() {
+        // Tiny buffer capacity so ranges get split across reads.
+        let input = BufReader::with_capacity(3, "11-22,95-115,998-1012".as_bytes());
+        assert_eq!(stream_totals(input, &Rule::PART1), (4, 11 + 22 + 99 + 1010));
+        let input = "11-22,95-115,998-1012".as_bytes();
+        assert_eq!(
+            stream_totals(input, &Rule::PART2),
+            (6, 11 + 22 + 99 + 111 + 999 + 1010)
+        );
+    }
+
+    #[test]
+    fn test_get_digits() {
+        assert_eq!(get_digits(0, 10), vec![0]);
+        assert_eq!(get_digits(101, 10), vec![1, 0, 1]);
+        assert_eq!(get_digits(6, 2), vec![1, 1, 0]);
+        assert_eq!(get_digits(255, 16), vec![15, 15]);
     }
 
     #[test]
     fn test_check_invalid() {
-        assert_eq!(check_invalid(1), false);
-        assert_eq!(check_invalid(10), false);
-        assert_eq!(check_invalid(11), true);
-        assert_eq!(check_invalid(111), false);
+        assert_eq!(Rule::PART1.is_invalid(1), false);
+        assert_eq!(Rule::PART1.is_invalid(10), false);
+        assert_eq!(Rule::PART1.is_invalid(11), true);
+        assert_eq!(Rule::PART1.is_invalid(111), false);
+        assert_eq!(Rule::PART1.is_invalid(1111), true);
+    }
+
+    /// The original part 1 check, kept apart from Rule so the arithmetic is
+    /// tested against code it doesn't share. Splits the digits in half as text.
+    fn halves_match(number: u64) -> bool {
+        let characters: Vec<char> = number.to_string().chars().collect();
+        let (left, right) = characters.split_at(characters.len() / 2);
+        characters.len().is_multiple_of(2) && left == right
+    }
+
+    /// The original part 2 check. Splits the digits as text into every chunk
+    /// size that divides them evenly and checks if all the chunks match.
+    fn chunks_match(number: u64) -> bool {
+        let characters: Vec<char> = number.to_string().chars().collect();
+        (1..=characters.len() / 2)
+            .filter(|chunk_size| characters.len().is_multiple_of(*chunk_size))
+            .any(|chunk_size| {
+                let mut chunks = characters.chunks(chunk_size);
+                let first = chunks.next().unwrap();
+                chunks.all(|chunk| chunk == first)
+            })
+    }
+
+    /// Every ID in the range the check says is invalid.
+    fn brute_force(start: u64, end: u64, check: fn(u64) -> bool) -> Vec<u64> {
+        (start..=end).filter(|&id| check(id)).collect()
     }
 
     #[test]
     fn test_find_invalids() {
-        let mut id = ProductID::new(11, 22);
-        id.find_invalids();
-        assert_eq!(id.invalid_ids, vec![11, 22]);
+        assert_eq!(brute_force(11, 22, halves_match), vec![11, 22]);
+        assert_eq!(
+            ProductID::new(11, 22)
+                .scan_invalids(&Rule::PART1)
+                .collect::<Vec<_>>(),
+            vec![11, 22]
+        );
     }
 
     /// Struggled on part2 to get the example result and had to do each example
     /// individually.
     #[test]
     fn test_find_invalids2() {
-        let mut id = ProductID::new(11, 22);
-        id.find_invalids2();
-        assert_eq!(id.invalid_ids2, vec![11, 22]);
-        let mut id = ProductID::new(95, 115);
-        id.find_invalids2();
-        assert_eq!(id.invalid_ids2, vec![99, 111]);
-        let mut id = ProductID::new(998, 1012);
-        id.find_invalids2();
-        assert_eq!(id.invalid_ids2, vec![999, 1010]);
-        let mut id = ProductID::new(1188511880, 1188511890);
-        id.find_invalids2();
-        assert_eq!(id.invalid_ids2, vec![1188511885]);
-        let mut id = ProductID::new(222220, 222224);
-        id.find_invalids2();
-        assert_eq!(id.invalid_ids2, vec![222222]);
-        let mut id = ProductID::new(1698522, 1698528);
-        id.find_invalids2();
-        assert_eq!(id.invalid_ids2, vec![]);
+        for (start, end, expected) in [
+            (11, 22, vec![11, 22]),
+            (95, 115, vec![99, 111]),
+            (998, 1012, vec![999, 1010]),
+            (1188511880, 1188511890, vec![1188511885]),
+            (222220, 222224, vec![222222]),
+            (1698522, 1698528, vec![]),
+        ] {
+            assert_eq!(brute_force(start, end, chunks_match), expected);
+            let id = ProductID::new(start, end);
+            assert_eq!(id.scan_invalids(&Rule::PART2).collect::<Vec<_>>(), expected);
+        }
     }
 
     #[test]
-    fn test_get_chunk_sizes() {
-        let number = 1;
-        assert_eq!(get_chunk_sizes(number), vec![]);
-        let number = 11;
-        assert_eq!(get_chunk_sizes(number), vec![1]);
-        let number = 111;
-        assert_eq!(get_chunk_sizes(number), vec![1]);
-        let number = 1111;
-        assert_eq!(get_chunk_sizes(number), vec![1, 2]);
+    fn test_invalid_totals() {
+        // Compare against the brute force version.
+        for (start, end) in [
+            (11, 22),
+            (95, 115),
+            (998, 1012),
+            (1, 100000),
+            (222220, 222224),
+        ] {
+            let invalids = brute_force(start, end, halves_match);
+            let expected = (
+                invalids.len() as u128,
+                invalids.iter().map(|&x| u128::from(x)).sum(),
+            );
+            let id = ProductID::new(start, end);
+            assert_eq!(id.invalid_totals(&Rule::PART1), expected, "{start}-{end}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_totals2() {
+        // Compare against the brute force version.
+        for (start, end) in [
+            (11, 22),
+            (95, 115),
+            (998, 1012),
+            (1, 1000000),
+            (1188511880, 1188511890),
+            (1698522, 1698528),
+        ] {
+            let invalids = brute_force(start, end, chunks_match);
+            let expected = (
+                invalids.len() as u128,
+                invalids.iter().map(|&x| u128::from(x)).sum(),
+            );
+            let id = ProductID::new(start, end);
+            assert_eq!(id.invalid_totals(&Rule::PART2), expected, "{start}-{end}");
+        }
+    }
+
+    /// The arithmetic has to agree with scanning for every base and repeat rule.
+    #[test]
+    fn test_invalid_totals_rules() {
+        let id = ProductID::new(1, 200000);
+        for base in [2, 3, 7, 10, 16] {
+            for pattern in [
+                Pattern::ExactRepeats(2),
+                Pattern::ExactRepeats(3),
+                Pattern::MinRepeats(2),
+                Pattern::MinRepeats(3),
+            ] {
+                let rule = Rule::new(pattern, base);
+                let invalids: Vec<u64> = id.scan_invalids(&rule).collect();
+                let expected = (
+                    invalids.len() as u128,
+                    invalids.iter().map(|&x| u128::from(x)).sum(),
+                );
+                assert_eq!(id.invalid_totals(&rule), expected, "{rule:?}");
+            }
+        }
+    }
+
+    /// Ranges this big would never finish with find_invalids.
+    #[test]
+    fn test_invalid_totals_huge_range() {
+        let id = ProductID::new(1, u64::MAX);
+        // One invalid ID per half for each even digit count up to 18 digits, plus
+        // the 20 digit ones that fit under u64::MAX.
+        let (count, _) = id.invalid_totals(&Rule::PART1);
+        let expected: u128 = (1..=9).map(|half| 9 * 10_u128.pow(half - 1)).sum::<u128>()
+            + u128::from(u64::MAX / 10000000001 - 999999999);
+        assert_eq!(count, expected);
+        // Repeats of 1, 11, 1111, 11111 and 1111111111 blocks, but only counted once.
+        let (count, sum) =
+            ProductID::new(11111111111111111111, 11111111111111111111).invalid_totals(&Rule::PART2);
+        assert_eq!((count, sum), (1, 11111111111111111111));
+        // Every base 2 number is 1 followed by anything, so 1 repeated 64 times is
+        // the only 64 digit one made of 1 digit blocks.
+        let (count, sum) = id.invalid_totals(&Rule::new(Pattern::ExactRepeats(64), 2));
+        assert_eq!((count, sum), (1, u128::from(u64::MAX)));
     }
 
     #[test]
-    fn test_get_chunks() {
-        assert_eq!(get_chunks(11, 1), vec![vec!['1'], vec!['1']]);
-        assert_eq!(get_chunks(12, 1), vec![vec!['1'], vec!['2']]);
+    fn test_palindrome_rule() {
+        let rule = Rule::new(Pattern::Palindrome, 10);
+        assert!(rule.is_invalid(7));
+        assert!(rule.is_invalid(121));
+        assert!(rule.is_invalid(1221));
+        assert!(!rule.is_invalid(123));
+        // 5 is 101 in base 2.
+        assert!(Rule::new(Pattern::Palindrome, 2).is_invalid(5));
+        assert!(!Rule::new(Pattern::Palindrome, 2).is_invalid(6));
         assert_eq!(
-            get_chunks(1111, 1),
-            vec![vec!['1'], vec!['1'], vec!['1'], vec!['1']]
+            ProductID::new(100, 131).invalid_totals(&rule),
+            (4, 101 + 111 + 121 + 131)
         );
-        assert_eq!(get_chunks(1111, 2), vec![vec!['1', '1'], vec!['1', '1']]);
     }
 
     #[test]
-    fn test_check_invalid2() {
-        assert_eq!(check_invalid2(&vec![vec!['1'], vec!['1']]), true);
-        assert_eq!(check_invalid2(&vec![vec!['2'], vec!['1']]), false);
-        assert_eq!(check_invalid2(&vec![vec!['1', '1'], vec!['1', '1']]), true);
+    fn test_rotated_blocks_rule() {
+        let rule = Rule::new(Pattern::RotatedBlocks(3), 10);
+        assert!(rule.is_invalid(123231312));
+        assert!(rule.is_invalid(111));
+        assert!(!rule.is_invalid(123123123));
+        assert!(!rule.is_invalid(1234));
+        let rule = Rule::new(Pattern::RotatedBlocks(2), 10);
+        assert!(rule.is_invalid(1221));
+        assert!(rule.is_invalid(123231));
         assert_eq!(
-            check_invalid2(&vec![vec!['1'], vec!['1'], vec!['1'], vec!['1']]),
-            true
+            ProductID::new(10, 30)
+                .scan_invalids(&rule)
+                .collect::<Vec<_>>(),
+            vec![11, 22]
         );
     }
+
+    #[test]
+    fn test_pattern_try_from() {
+        assert_eq!(Pattern::try_from("exact:2"), Ok(Pattern::ExactRepeats(2)));
+        assert_eq!(Pattern::try_from("min:3"), Ok(Pattern::MinRepeats(3)));
+        assert_eq!(Pattern::try_from("palindrome"), Ok(Pattern::Palindrome));
+        assert_eq!(
+            Pattern::try_from("rotated:3"),
+            Ok(Pattern::RotatedBlocks(3))
+        );
+        assert!(Pattern::try_from("min").is_err());
+        assert!(Pattern::try_from("min:x").is_err());
+        assert!(Pattern::try_from("most:2").is_err());
+        for pattern in [
+            "exact:0",
+            "exact:1",
+            "min:0",
+            "min:1",
+            "rotated:0",
+            "rotated:1",
+        ] {
+            assert!(Pattern::try_from(pattern).is_err(), "{pattern}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Rule::new got fewer than 2 repeats.")]
+    fn test_rule_too_few_repeats() {
+        let _ = Rule::new(Pattern::MinRepeats(1), 10);
+    }
+
+    /// The smallest repeats allowed have to agree with counting is_invalid one
+    /// ID at a time, including single digit IDs.
+    #[test]
+    fn test_invalid_totals_fewest_repeats() {
+        let id = ProductID::new(0, 5000);
+        for pattern in ["exact:2", "min:2", "rotated:2"] {
+            for base in [2, 10] {
+                let rule = Rule::new(Pattern::try_from(pattern).unwrap(), base);
+                let expected = (id.start..=id.end)
+                    .filter(|&x| rule.is_invalid(x))
+                    .fold((0, 0), |(count, sum), x| (count + 1, sum + u128::from(x)));
+                assert_eq!(id.invalid_totals(&rule), expected, "{rule:?}");
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Rule::new got base out of range.")]
+    fn test_rule_bad_base() {
+        let _ = Rule::new(Pattern::Palindrome, 1);
+    }
+
+    #[test]
+    fn test_mobius() {
+        let expected = [1, -1, -1, 0, -1, 1, -1, 0, 0, 1, -1, 0];
+        for (n, mu) in (1..=12).zip(expected) {
+            assert_eq!(mobius(n), mu, "mobius({n})");
+        }
+    }
+
+    #[test]
+    fn test_repeat_multiplier() {
+        assert_eq!(repeat_multiplier(2, 1, 10), 11);
+        assert_eq!(repeat_multiplier(6, 2, 10), 10101);
+        assert_eq!(repeat_multiplier(6, 3, 10), 1001);
+        assert_eq!(repeat_multiplier(4, 2, 2), 0b101);
+    }
+
+    #[test]
+    fn test_get_chunk_sizes() {
+        let pattern = Rule::PART2.pattern;
+        assert_eq!(pattern.block_sizes(1), vec![]);
+        assert_eq!(pattern.block_sizes(2), vec![1]);
+        assert_eq!(pattern.block_sizes(3), vec![1]);
+        assert_eq!(pattern.block_sizes(4), vec![1, 2]);
+        assert_eq!(Pattern::MinRepeats(3).block_sizes(6), vec![1, 2]);
+        assert_eq!(Pattern::ExactRepeats(3).block_sizes(6), vec![2]);
+        assert_eq!(Pattern::ExactRepeats(4).block_sizes(6), vec![]);
+    }
+
+    #[test]
+    fn test_check_invalid2() {
+        assert_eq!(blocks_match(&[1, 1], 1), true);
+        assert_eq!(blocks_match(&[2, 1], 1), false);
+        assert_eq!(blocks_match(&[1, 1, 1, 1], 2), true);
+        assert_eq!(blocks_match(&[1, 1, 1, 1], 1), true);
+        assert_eq!(blocks_match(&[1, 2, 1, 3], 2), false);
+        // A single block isn't a repeat.
+        assert_eq!(blocks_match(&[1, 2], 2), false);
+    }
 }
@@ -1,5 +1,7 @@
 //! Day 03: Lobby
 
+use std::fmt;
+
 /// Struct representing a battery bank.
 #[derive(Debug, Eq, Hash, PartialEq)]
 struct Bank {
@@ -13,46 +15,314 @@ impl Bank {
         Self { joltages }
     }
 
-    /// Find the largest joltage in the range provided. (Jolts,pos)
-    fn find_largest_in_range(&self, start: usize, end: usize) -> (u8, usize) {
-        let mut largest = u8::MIN;
-        let mut largest_pos = usize::MIN;
-        for (pos, joltage) in self.joltages[start..=end].iter().enumerate() {
-            if joltage > &largest {
-                largest = *joltage;
-                largest_pos = pos + start;
+    /// Pick the `digits` batteries, in bank order, that make the largest joltage.
+    /// Uses a monotonic stack so it's O(n) for any number of digits: while a
+    /// bigger joltage comes along and we can still afford to drop batteries, the
+    /// smaller ones before it get dropped.
+    fn select_largest(&self, digits: usize) -> Selection {
+        assert!(
+            digits <= self.joltages.len(),
+            "Bank::select_largest asked for more digits than batteries."
+        );
+        let mut can_drop = self.joltages.len() - digits;
+        let mut stack: Vec<usize> = Vec::with_capacity(self.joltages.len());
+        for (pos, &joltage) in self.joltages.iter().enumerate() {
+            while can_drop > 0
+                && stack
+                    .last()
+                    .is_some_and(|&top| self.joltages[top] < joltage)
+            {
+                stack.pop();
+                can_drop -= 1;
             }
+            stack.push(pos);
+        }
+        // Anything we didn't get to drop comes off the end since those are the
+        // lowest value digits.
+        stack.truncate(digits);
 
-            // Stop iterating if we found a 9 since can't be higher.
-            if largest == 9 {
-                break;
-            }
+        Selection {
+            joltages: stack.iter().map(|&pos| self.joltages[pos]).collect(),
+            positions: stack,
         }
-        (largest, largest_pos)
     }
 
     /// Find the largest joltage given the number of digits/batteries to use.
     fn find_largest_by_num_digits(&self, digits: usize) -> u64 {
-        let mut ordered_digits: Vec<u8> = Vec::with_capacity(digits);
+        match self.select_largest(digits).value() {
+            Joltage::Number(number) => u64::try_from(number).expect("Joltage doesn't fit in u64."),
+            Joltage::Digits(_) => panic!("Joltage doesn't fit in u64."),
+        }
+    }
+
+    /// Pick the `digits` batteries, in bank order, that make the smallest or
+    /// largest joltage allowed by the constraint. Returns None if the constraint
+    /// makes it impossible to pick that many.
+    ///
+    /// A DP table of which (position, batteries left, skips left) states can still
+    /// be finished lets us pick greedily instead of comparing every selection.
+    fn select_best(
+        &self,
+        digits: usize,
+        objective: Objective,
+        constraint: Constraint,
+    ) -> Option<Selection> {
+        let len = self.joltages.len();
+        // Positions to move past a picked battery, and the skip budget if any.
+        let (step, max_skips) = match constraint {
+            Constraint::Unconstrained => (1, None),
+            Constraint::NoAdjacent => (2, None),
+            Constraint::MaxSkips(skips) => (1, Some(skips)),
+        };
+        // Without a budget skips are free, so only keep one budget slot.
+        let budgets = max_skips.map_or(1, |skips| skips + 1);
+        let index =
+            |pos: usize, left: usize, budget: usize| (pos * (digits + 1) + left) * budgets + budget;
+
+        // can_finish[index(pos, left, budget)] is true if `left` more batteries can
+        // be picked with the next one at or after `pos` and `budget` skips left.
+        let mut can_finish = vec![false; (len + step + 1) * (digits + 1) * budgets];
+        for pos in (0..len + step + 1).rev() {
+            for left in 0..=digits {
+                for budget in 0..budgets {
+                    can_finish[index(pos, left, budget)] = left == 0
+                        || (pos < len
+                            && (can_finish[index(pos + step, left - 1, budget)]
+                                || match max_skips {
+                                    None => can_finish[index(pos + 1, left, budget)],
+                                    Some(_) => {
+                                        budget > 0 && can_finish[index(pos + 1, left, budget - 1)]
+                                    }
+                                }));
+                }
+            }
+        }
+
+        let joltages_at = |positions: &[usize]| -> Vec<u8> {
+            positions.iter().map(|&pos| self.joltages[pos]).collect()
+        };
+        let is_better = |new: &[u8], old: &[u8]| match objective {
+            Objective::Smallest => new < old,
+            Objective::Largest => new > old,
+        };
+
+        // Finish a selection after the first battery. Each pick takes the best
+        // joltage that can still be finished, using the earliest one on ties since
+        // it has the same or more skips left and reaches everything a later one can.
+        let finish_from = |first: usize| -> Vec<usize> {
+            let mut positions: Vec<usize> = Vec::with_capacity(digits);
+            positions.push(first);
+            let mut start = first + step;
+            let mut budget = max_skips.unwrap_or(0);
+            for left in (1..digits).rev() {
+                let last = match max_skips {
+                    Some(_) => (start + budget).min(len - 1),
+                    None => len - 1,
+                };
+                let mut chosen: Option<(usize, usize)> = None;
+                for pos in start..=last {
+                    let budget_after = match max_skips {
+                        Some(_) => budget - (pos - start),
+                        None => budget,
+                    };
+                    if !can_finish[index(pos + step, left - 1, budget_after)] {
+                        continue;
+                    }
+                    if chosen.is_none_or(|(best, _)| {
+                        is_better(&[self.joltages[pos]], &[self.joltages[best]])
+                    }) {
+                        chosen = Some((pos, budget_after));
+                    }
+                }
+                let (pos, budget_after) =
+                    chosen.expect("select_best couldn't finish a finishable selection.");
+                positions.push(pos);
+                start = pos + step;
+                budget = budget_after;
+            }
+            positions
+        };
+
+        if digits == 0 {
+            return Some(Selection {
+                positions: vec![],
+                joltages: vec![],
+            });
+        }
+
+        // The first pick is free, so with a skip budget an earlier battery doesn't
+        // reach everything a later one with the same joltage can. Try each of the
+        // best first batteries then. Otherwise the earliest one is enough.
+        let budget = max_skips.unwrap_or(0);
+        let firsts: Vec<usize> = (0..len)
+            .filter(|&pos| can_finish[index(pos + step, digits - 1, budget)])
+            .collect();
+        let best_first = firsts.iter().copied().reduce(|best, pos| {
+            if is_better(&[self.joltages[pos]], &[self.joltages[best]]) {
+                pos
+            } else {
+                best
+            }
+        })?;
+        let mut best: Option<Vec<usize>> = None;
+        for first in firsts {
+            if self.joltages[first] != self.joltages[best_first] {
+                continue;
+            }
+            let positions = finish_from(first);
+            if best
+                .as_ref()
+                .is_none_or(|best| is_better(&joltages_at(&positions), &joltages_at(best)))
+            {
+                best = Some(positions);
+            }
+            if max_skips.is_none() {
+                break;
+            }
+        }
+
+        best.map(|positions| Selection {
+            joltages: joltages_at(&positions),
+            positions,
+        })
+    }
+
+    /// Tables for counting distinct joltages. `next[i][j]` is the first position at
+    /// or after i with joltage j. `counts[i][d]` is how many distinct `d` digit
+    /// joltages can be made from the batteries at i onwards, saturating at
+    /// u128::MAX.
+    fn distinct_tables(&self, digits: usize) -> (Vec<[Option<usize>; 10]>, Vec<Vec<u128>>) {
+        let len = self.joltages.len();
+        let mut next = vec![[None; 10]; len + 1];
+        for pos in (0..len).rev() {
+            next[pos] = next[pos + 1];
+            next[pos][usize::from(self.joltages[pos])] = Some(pos);
+        }
+
+        // Counting from the first position of each joltage only means every
+        // distinct joltage is counted once no matter how many ways it can be made.
+        let mut counts = vec![vec![0_u128; digits + 1]; len + 1];
+        for pos in (0..=len).rev() {
+            counts[pos][0] = 1;
+            for left in 1..=digits {
+                counts[pos][left] = next[pos].iter().flatten().fold(0_u128, |sum, &first| {
+                    sum.saturating_add(counts[first + 1][left - 1])
+                });
+            }
+        }
+        (next, counts)
+    }
+
+    /// Count of distinct joltages that can be made with `digits` batteries. None if
+    /// it doesn't fit in a u128.
+    fn count_distinct(&self, digits: usize) -> Option<u128> {
+        let (_, counts) = self.distinct_tables(digits);
+        Some(counts[0][digits]).filter(|&count| count != u128::MAX)
+    }
+
+    /// Pick the batteries that make the k-th largest distinct joltage, where k = 1
+    /// is the largest. Returns None if there aren't k distinct joltages.
+    fn select_kth_largest(&self, digits: usize, mut k: u128) -> Option<Selection> {
+        // There's only one way to pick no batteries.
+        if k == 0 || (digits == 0 && k > 1) {
+            return None;
+        }
+        let (next, counts) = self.distinct_tables(digits);
+        let mut positions: Vec<usize> = Vec::with_capacity(digits);
         let mut start = 0;
-        for digit in (0..digits).rev() {
-            let (num, pos) = self.find_largest_in_range(start, self.joltages.len() - 1 - digit);
+        for left in (1..=digits).rev() {
+            // Joltages starting with a bigger digit all come first, so skip over
+            // them until k lands inside one digit's group.
+            let mut chosen = None;
+            for first in next[start].iter().rev().flatten() {
+                let count = counts[first + 1][left - 1];
+                if k <= count {
+                    chosen = Some(*first);
+                    break;
+                }
+                k -= count;
+            }
+            let pos = chosen?;
+            positions.push(pos);
             start = pos + 1;
-            ordered_digits.push(num);
         }
 
-        combine_u8s_to_u64(ordered_digits)
+        Some(Selection {
+            joltages: positions.iter().map(|&pos| self.joltages[pos]).collect(),
+            positions,
+        })
     }
 }
 
-/// Take a vec of u8's and combine them into a single number. Vec[0] = 1's digit.
-fn combine_u8s_to_u64(digits: Vec<u8>) -> u64 {
-    let mut ret: u64 = 0;
-    for (pos, digit) in digits.into_iter().rev().enumerate() {
-        let digit = digit;
-        ret += digit as u64 * 10_u64.pow(pos as u32);
+/// Whether select_best looks for the smallest or largest joltage.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Objective {
+    Smallest,
+    Largest,
+}
+
+/// Extra rule on which batteries select_best can pick.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Constraint {
+    /// Any batteries as long as they're in bank order.
+    Unconstrained,
+    /// No two picked batteries can be next to each other in the bank.
+    NoAdjacent,
+    /// At most this many batteries left out between the first and last picked
+    /// battery.
+    MaxSkips(usize),
+}
+
+/// Batteries picked from a bank, in bank order.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct Selection {
+    /// Index of each picked battery in the bank.
+    positions: Vec<usize>,
+    /// Joltage of each picked battery.
+    joltages: Vec<u8>,
+}
+
+impl Selection {
+    /// Combined joltage of the picked batteries. Falls back to a decimal string
+    /// when there are too many digits for a u128.
+    fn value(&self) -> Joltage {
+        match combine_u8s_to_u128(&self.joltages) {
+            Some(number) => Joltage::Number(number),
+            None => Joltage::Digits(
+                self.joltages
+                    .iter()
+                    .map(|&digit| char::from(b'0' + digit))
+                    .collect(),
+            ),
+        }
     }
-    ret
+}
+
+/// Combined joltage of a selection of batteries.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum Joltage {
+    /// Joltage small enough to fit in a u128.
+    Number(u128),
+    /// Decimal digits of a joltage too big for a u128.
+    Digits(String),
+}
+
+impl fmt::Display for Joltage {
+    /// Format the joltage the same way no matter how it's stored.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Joltage::Number(number) => write!(f, "{number}"),
+            Joltage::Digits(digits) => write!(f, "{digits}"),
+        }
+    }
+}
+
+/// Take a slice of u8's and combine them into a single number. Last item = 1's
+/// digit. Returns None instead of overflowing.
+fn combine_u8s_to_u128(digits: &[u8]) -> Option<u128> {
+    digits.iter().try_fold(0_u128, |ret, &digit| {
+        ret.checked_mul(10)?.checked_add(u128::from(digit))
+    })
 }
 
 /// Parse the incoming file to Vec of Bank's
@@ -99,8 +369,72 @@ fn part2(file_name: &str) -> u64 {
     sum
 }
 
+/// Run one of the selection queries against every bank in the file. Returns the
+/// text to print, one line per bank.
+/// - `smallest|largest|no-adjacent <file> <digits>`
+/// - `max-skips <file> <digits> <skips>`
+/// - `kth <file> <digits> <k>`
+/// - `count <file> <digits>`
+fn run_query(args: &[String]) -> String {
+    let usage =
+        "Usage: day03 <smallest|largest|no-adjacent|max-skips|kth|count> <file> <digits> [skips|k]";
+    let [command, file_name, digits, rest @ ..] = args else {
+        panic!("{usage}");
+    };
+    let digits: usize = digits.parse().expect("Failed to parse digits.");
+    let extra = || -> usize {
+        rest.first()
+            .expect(usage)
+            .parse()
+            .expect("Failed to parse number.")
+    };
+    let file_contents = std::fs::read_to_string(file_name).expect("Couldn't open file");
+    let banks = parse_text(&file_contents);
+
+    let describe = |selection: Option<Selection>| match selection {
+        Some(selection) => format!("{} at {:?}", selection.value(), selection.positions),
+        None => "none".to_string(),
+    };
+    let mut ret = String::new();
+    for bank in banks {
+        let line = match command.as_str() {
+            "smallest" => {
+                describe(bank.select_best(digits, Objective::Smallest, Constraint::Unconstrained))
+            }
+            "largest" => {
+                describe(bank.select_best(digits, Objective::Largest, Constraint::Unconstrained))
+            }
+            "no-adjacent" => {
+                describe(bank.select_best(digits, Objective::Largest, Constraint::NoAdjacent))
+            }
+            "max-skips" => describe(bank.select_best(
+                digits,
+                Objective::Largest,
+                Constraint::MaxSkips(extra()),
+            )),
+            "kth" => describe(bank.select_kth_largest(digits, extra() as u128)),
+            "count" => match bank.count_distinct(digits) {
+                Some(count) => count.to_string(),
+                None => "more than u128::MAX".to_string(),
+            },
+            _ => panic!("{usage}"),
+        };
+        ret.push_str(&line);
+        ret.push('\n');
+    }
+    ret
+}
+
 /// Main function / code entry point.
 fn main() {
+    // Any arguments means we're running a selection query instead of the puzzle
+    // answers.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        print!("{}", run_query(&args));
+        return;
+    }
+
     println!("Sum for example1: {}", part1("example1.txt"));
     println!("Sum for input: {}", part1("input.txt"));
     println!("Sum for example1: {}", part2("example1.txt"));
@@ -141,14 +475,191 @@ mod tests {
     }
 
     #[test]
-    fn test_find_largest_in_range() {
+    fn test_select_largest() {
         let bank = Bank::new(vec![9, 8, 7, 6, 9]);
-        assert_eq!(bank.find_largest_in_range(1, 2), (8, 1));
+        let selection = bank.select_largest(2);
+        assert_eq!(selection.positions, vec![0, 4]);
+        assert_eq!(selection.joltages, vec![9, 9]);
+        let bank = Bank::new(vec![2, 3, 4, 2, 7, 8]);
+        assert_eq!(bank.select_largest(3).positions, vec![2, 4, 5]);
+        assert_eq!(bank.select_largest(6).positions, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(bank.select_largest(0).positions, vec![]);
+        // Equal joltages keep the earliest ones.
+        let bank = Bank::new(vec![5, 5, 5]);
+        assert_eq!(bank.select_largest(2).positions, vec![0, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Bank::select_largest asked for more digits than batteries.")]
+    fn test_select_largest_too_many_digits() {
+        Bank::new(vec![1, 2]).select_largest(3);
+    }
+
+    /// Every way to pick `digits` batteries, as (positions, value), for checking
+    /// the DP answers against.
+    fn brute_force(bank: &Bank, digits: usize) -> Vec<(Vec<usize>, u128)> {
+        let len = bank.joltages.len();
+        (0_u32..1 << len)
+            .filter(|mask| mask.count_ones() as usize == digits)
+            .map(|mask| {
+                let positions: Vec<usize> = (0..len).filter(|i| mask & (1 << i) != 0).collect();
+                let joltages: Vec<u8> = positions.iter().map(|&i| bank.joltages[i]).collect();
+                (positions, combine_u8s_to_u128(&joltages).unwrap())
+            })
+            .collect()
+    }
+
+    /// Skips between the first and last picked battery.
+    fn skips(positions: &[usize]) -> usize {
+        match (positions.first(), positions.last()) {
+            (Some(first), Some(last)) => last - first + 1 - positions.len(),
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn test_select_best() {
+        let banks = [
+            Bank::new(vec![2, 3, 4, 2, 7, 8]),
+            Bank::new(vec![9, 8, 7, 6, 9]),
+            Bank::new(vec![1, 9, 1, 9, 1, 9, 5, 5]),
+            Bank::new(vec![5, 1, 5, 1, 1, 5]),
+        ];
+        for bank in &banks {
+            for digits in 0..=bank.joltages.len() {
+                let all = brute_force(bank, digits);
+                let value = |selection: Option<Selection>| {
+                    selection.map(|s| match s.value() {
+                        Joltage::Number(number) => number,
+                        Joltage::Digits(_) => unreachable!(),
+                    })
+                };
+                let best = |filter: &dyn Fn(&[usize]) -> bool, objective: Objective| {
+                    let values = all.iter().filter(|(p, _)| filter(p)).map(|(_, v)| *v);
+                    match objective {
+                        Objective::Smallest => values.min(),
+                        Objective::Largest => values.max(),
+                    }
+                };
+                let no_adjacent = |p: &[usize]| p.windows(2).all(|w| w[1] - w[0] > 1);
+
+                for objective in [Objective::Smallest, Objective::Largest] {
+                    let selection = bank.select_best(digits, objective, Constraint::Unconstrained);
+                    assert_eq!(
+                        value(selection),
+                        best(&|_| true, objective),
+                        "{bank:?} {digits}"
+                    );
+                    let selection = bank.select_best(digits, objective, Constraint::NoAdjacent);
+                    assert_eq!(
+                        value(selection),
+                        best(&no_adjacent, objective),
+                        "{bank:?} {digits}"
+                    );
+                    for max_skips in 0..3 {
+                        let constraint = Constraint::MaxSkips(max_skips);
+                        let selection = bank.select_best(digits, objective, constraint);
+                        if let Some(selection) = &selection {
+                            assert!(skips(&selection.positions) <= max_skips);
+                        }
+                        assert_eq!(
+                            value(selection),
+                            best(&|p| skips(p) <= max_skips, objective),
+                            "{bank:?} {digits} {max_skips}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_select_best_examples() {
+        let bank = Bank::new(vec![2, 3, 4, 2, 7, 8]);
+        let smallest = bank.select_best(3, Objective::Smallest, Constraint::Unconstrained);
+        assert_eq!(smallest.unwrap().positions, vec![0, 3, 4]);
+        let no_adjacent = bank.select_best(3, Objective::Largest, Constraint::NoAdjacent);
+        assert_eq!(no_adjacent.unwrap().joltages, vec![3, 2, 8]);
+        // Too many batteries to pick without any being next to each other.
+        assert_eq!(
+            bank.select_best(4, Objective::Largest, Constraint::NoAdjacent),
+            None
+        );
+        // Same as select_largest without a constraint.
+        assert_eq!(
+            bank.select_best(3, Objective::Largest, Constraint::Unconstrained),
+            Some(bank.select_largest(3))
+        );
+    }
+
+    #[test]
+    fn test_count_distinct() {
+        for bank in [
+            Bank::new(vec![2, 3, 4, 2, 7, 8]),
+            Bank::new(vec![1, 9, 1, 9, 1, 9, 5, 5]),
+            Bank::new(vec![5, 5, 5, 5]),
+        ] {
+            for digits in 0..=bank.joltages.len() + 1 {
+                let mut values: Vec<u128> = brute_force(&bank, digits)
+                    .into_iter()
+                    .map(|(_, v)| v)
+                    .collect();
+                values.sort();
+                values.dedup();
+                assert_eq!(bank.count_distinct(digits), Some(values.len() as u128));
+            }
+        }
+        // Picking 200 of 400 alternating 1s and 2s makes way more distinct
+        // joltages than fit in a u128.
+        let bank = Bank::new([1, 2].repeat(200));
+        assert_eq!(bank.count_distinct(200), None);
+    }
+
+    #[test]
+    fn test_select_kth_largest() {
+        let bank = Bank::new(vec![1, 9, 1, 9, 1, 9, 5, 5]);
+        for digits in 1..=4 {
+            let mut values: Vec<u128> = brute_force(&bank, digits)
+                .into_iter()
+                .map(|(_, v)| v)
+                .collect();
+            values.sort();
+            values.dedup();
+            values.reverse();
+            for (k, expected) in values.iter().enumerate() {
+                let selection = bank.select_kth_largest(digits, k as u128 + 1).unwrap();
+                assert_eq!(selection.value(), Joltage::Number(*expected));
+            }
+            assert_eq!(
+                bank.select_kth_largest(digits, values.len() as u128 + 1),
+                None
+            );
+        }
+        assert_eq!(bank.select_kth_largest(2, 0), None);
+        assert!(bank.select_kth_largest(0, 1).is_some());
+        assert_eq!(bank.select_kth_largest(0, 2), None);
+        assert_eq!(bank.select_kth_largest(2, 1), Some(bank.select_largest(2)));
+    }
+
+    #[test]
+    fn test_selection_value() {
+        let bank = Bank::new(vec![9; 50]);
+        assert_eq!(
+            bank.select_largest(38).value(),
+            Joltage::Number(10_u128.pow(38) - 1)
+        );
+        // 40 nines is too big for u128.
+        let value = bank.select_largest(40).value();
+        assert_eq!(value, Joltage::Digits("9".repeat(40)));
+        assert_eq!(value.to_string(), "9".repeat(40));
+        assert_eq!(Joltage::Number(1234).to_string(), "1234");
     }
 
     #[test]
-    fn test_combine_u8s_to_u64() {
-        assert_eq!(combine_u8s_to_u64(vec![1, 2, 3, 4]), 1234)
+    fn test_combine_u8s_to_u128() {
+        assert_eq!(combine_u8s_to_u128(&[1, 2, 3, 4]), Some(1234));
+        assert_eq!(combine_u8s_to_u128(&[]), Some(0));
+        assert_eq!(combine_u8s_to_u128(&[9; 40]), None);
     }
 
     #[test]
@@ -1,61 +1,377 @@
 //! Day 04: Printing Department
 
-use std::collections::HashSet;
-use utilities::{coord::Coord, grid::Grid};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+use utilities::{
+    coord::Coord,
+    gif::{GifWriter, Palette},
+    grid::Grid,
+    viz::{Animator, Frame, stdin_controls},
+};
 
-/// Get all the locations of paper.
-fn get_papers(grid: &Grid) -> HashSet<Coord> {
-    let mut papers = HashSet::new();
-    for (coord, character) in grid.char_map.iter() {
-        if character == &'@' {
-            papers.insert(*coord);
+/// Which cells around a paper count as nearby.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Neighbourhood {
+    /// North, east, south and west.
+    Four,
+    /// All 8 surrounding cells.
+    Eight,
+    /// Any offsets from the paper. They don't have to be symmetric.
+    Stencil(Vec<Coord>),
+}
+
+impl Neighbourhood {
+    /// Offsets from a paper to the cells nearby.
+    fn offsets(&self) -> Vec<Coord> {
+        let origin = Coord::new(0, 0);
+        match self {
+            Self::Four => vec![origin.north(), origin.east(), origin.south(), origin.west()],
+            Self::Eight => origin.surrounding().to_vec(),
+            Self::Stencil(offsets) => offsets.clone(),
+        }
+    }
+}
+
+impl TryFrom<&str> for Neighbourhood {
+    type Error = &'static str;
+
+    /// Parse `4`, `8`, or a stencil of offsets like `0,-1;1,0;-2,0`.
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "4" => return Ok(Self::Four),
+            "8" => return Ok(Self::Eight),
+            _ => {}
+        }
+        let mut offsets = Vec::new();
+        for offset in value.split(';') {
+            let (x, y) = offset
+                .split_once(',')
+                .ok_or("Stencil offsets look like x,y;x,y")?;
+            let x = x
+                .trim()
+                .parse()
+                .map_err(|_| "Stencil offsets need whole numbers")?;
+            let y = y
+                .trim()
+                .parse()
+                .map_err(|_| "Stencil offsets need whole numbers")?;
+            let offset = Coord::new(x, y);
+            if offset == Coord::new(0, 0) {
+                return Err("A paper can't be its own neighbour");
+            }
+            if !offsets.contains(&offset) {
+                offsets.push(offset);
+            }
+        }
+        Ok(Self::Stencil(offsets))
+    }
+}
+
+/// When a paper can be reached by the forklifts.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Rule {
+    /// A paper with fewer than this many papers nearby can be reached.
+    threshold: usize,
+    neighbourhood: Neighbourhood,
+    /// Character on the grid that is a paper.
+    occupied: char,
+}
+
+impl Default for Rule {
+    /// The puzzle's rule: fewer than 4 of the 8 surrounding cells are `@`.
+    fn default() -> Self {
+        Self {
+            threshold: 4,
+            neighbourhood: Neighbourhood::Eight,
+            occupied: '@',
+        }
+    }
+}
+
+impl Rule {
+    /// Get all the locations of paper.
+    fn papers(&self, grid: &Grid) -> HashSet<Coord> {
+        grid.char_map
+            .iter()
+            .filter(|&(_, &character)| character == self.occupied)
+            .map(|(&coord, _)| coord)
+            .collect()
+    }
+
+    /// Count how many papers are nearby each paper.
+    fn neighbour_counts(&self, papers: &HashSet<Coord>) -> HashMap<Coord, usize> {
+        let offsets = self.neighbourhood.offsets();
+        papers
+            .iter()
+            .map(|&paper| {
+                let count = offsets
+                    .iter()
+                    .filter(|&&offset| papers.contains(&(paper + offset)))
+                    .count();
+                (paper, count)
+            })
+            .collect()
+    }
+
+    /// Papers that can be reached straight away.
+    fn accessible(&self, papers: &HashSet<Coord>) -> usize {
+        self.neighbour_counts(papers)
+            .values()
+            .filter(|&&count| count < self.threshold)
+            .count()
+    }
+
+    /// Keep removing the accessible papers until none are left to remove.
+    ///
+    /// Works from a worklist instead of checking every paper each round. Each
+    /// paper keeps a count of its neighbours, and removing a paper only touches
+    /// the counts of the papers it is nearby. A paper joins the next round when
+    /// its count drops below the threshold, so each one is handled at most once.
+    fn removal_history(&self, papers: &HashSet<Coord>) -> History {
+        let offsets = self.neighbourhood.offsets();
+        let mut counts = self.neighbour_counts(papers);
+        let mut round: HashSet<Coord> = counts
+            .iter()
+            .filter(|&(_, &count)| count < self.threshold)
+            .map(|(&paper, _)| paper)
+            .collect();
+        let mut history = History::default();
+        while !round.is_empty() {
+            // Take the whole round out first so papers in it don't count each other.
+            for paper in &round {
+                counts.remove(paper);
+            }
+            let mut next = HashSet::new();
+            for paper in &round {
+                // Stencils can be lopsided, so look for the papers that have
+                // this one nearby rather than the ones nearby it.
+                for &offset in &offsets {
+                    let other = *paper - offset;
+                    if let Some(count) = counts.get_mut(&other) {
+                        *count -= 1;
+                        if *count + 1 == self.threshold {
+                            next.insert(other);
+                        }
+                    }
+                }
+            }
+            history.rounds.push(round);
+            round = next;
         }
+        history
     }
-    papers
 }
 
-/// Get all papers with fewer than 4 papers nearby.
-fn get_accessible_papers(papers: &HashSet<Coord>) -> HashSet<Coord> {
-    papers
-        .into_iter()
-        .filter(|&&x| x.get_surrounding_coords().intersection(&papers).count() < 4)
-        .copied()
-        .collect()
+/// Which papers were removed in each round.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct History {
+    rounds: Vec<HashSet<Coord>>,
 }
 
-/// Remove the accessible_papers from the papers.
-fn remove_papers(papers: &mut HashSet<Coord>, accessible_papers: &HashSet<Coord>) {
-    for paper in accessible_papers {
-        papers.remove(&paper);
+impl History {
+    /// How many papers were removed in all.
+    fn total(&self) -> usize {
+        self.rounds.iter().map(HashSet::len).sum()
+    }
+
+    /// How many papers were removed in each round.
+    fn sizes(&self) -> Vec<usize> {
+        self.rounds.iter().map(HashSet::len).collect()
+    }
+
+    /// The round the paper was removed in, counting from 1.
+    fn round_of(&self, coord: &Coord) -> Option<usize> {
+        self.rounds
+            .iter()
+            .position(|round| round.contains(coord))
+            .map(|round| round + 1)
+    }
+
+    /// One `round,x,y` line per removed paper, in order of round then
+    /// position, with rounds counting from 1.
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("round,x,y\n");
+        for (round, removed) in self.rounds.iter().enumerate() {
+            let mut removed: Vec<&Coord> = removed.iter().collect();
+            removed.sort_by_key(|coord| (coord.y, coord.x));
+            for coord in removed {
+                csv += &format!("{},{},{}\n", round + 1, coord.x, coord.y);
+            }
+        }
+        csv
+    }
+
+    /// Draw the grid with each removed paper replaced by the round it went in,
+    /// as 1-9 then a-z, and `+` after that.
+    fn render(&self, grid: &Grid) -> String {
+        let mut lines = Vec::new();
+        for y in 0..=grid.max_y {
+            let mut line = String::new();
+            for x in 0..=grid.max_x {
+                let coord = Coord::new(x, y);
+                line.push(match self.round_of(&coord) {
+                    Some(round) => char::from_digit(round as u32, 36).unwrap_or('+'),
+                    None => *grid.char_map.get(&coord).unwrap_or(&' '),
+                });
+            }
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+
+    /// A frame for each round with the papers about to go highlighted, and a
+    /// last frame of what's left.
+    fn frames(&self, grid: &Grid) -> Vec<Frame> {
+        let mut grid = grid.clone();
+        let mut frames = Vec::new();
+        for (round, removed) in self.rounds.iter().enumerate() {
+            let caption = format!("Round {}: removing {}", round + 1, removed.len());
+            frames.push(
+                Frame::new(grid.clone())
+                    .with_highlights(removed.iter().copied())
+                    .with_caption(&caption),
+            );
+            for paper in removed {
+                grid.char_map.insert(*paper, '.');
+            }
+        }
+        frames.push(Frame::new(grid).with_caption("Done"));
+        frames
     }
 }
 
+/// Get all the locations of paper.
+fn get_papers(grid: &Grid) -> HashSet<Coord> {
+    Rule::default().papers(grid)
+}
+
 /// Find all @ locations with less than 4 @'s around them.
 fn part1(file_name: &str) -> usize {
     let grid = Grid::new_from_file(file_name);
-    let papers = get_papers(&grid);
-    let accessible_papers = get_accessible_papers(&papers);
-    accessible_papers.len()
+    Rule::default().accessible(&get_papers(&grid))
 }
 
 /// Do part1 until you can't do it anymore.
 fn part2(file_name: &str) -> usize {
     let grid = Grid::new_from_file(file_name);
-    let mut papers = get_papers(&grid);
-    let mut sum = 0;
-    loop {
-        let accessible_papers = get_accessible_papers(&papers);
-        if accessible_papers.len() <= 0 {
-            break;
+    Rule::default().removal_history(&get_papers(&grid)).total()
+}
+
+/// Colors for the GIF: paper in cream on a dark background.
+fn default_palette() -> Palette {
+    Palette::new([24, 24, 32])
+        .with('@', [240, 230, 200])
+        .with('.', [24, 24, 32])
+}
+
+/// Handle `day04 <file> [rule flags] [output flags]`. The rule flags are
+/// `--threshold n`, `--neighbourhood 4|8|x,y;x,y...` and `--occupied c`.
+///
+/// By default the rounds of paper removal are animated in the terminal, with
+/// `--step` and `--delay ms`. Instead, `--gif out.gif` saves them as a GIF
+/// (with `--palette spec`, `--scale px` and `--delay ms`), `--history` prints
+/// which papers went in each round as CSV, and `--map` draws the round each
+/// paper went in on the grid.
+fn run_query(args: &[String]) {
+    let usage = "Usage: day04 <file> [--threshold n] [--neighbourhood 4|8|x,y;...] \
+                 [--occupied c] [--step] [--delay ms] [--gif out.gif] [--palette spec] \
+                 [--scale px] [--history] [--map]";
+    let (file_name, flags) = args.split_first().expect(usage);
+    let mut rule = Rule::default();
+    let mut step = false;
+    let mut delay = Duration::from_millis(100);
+    let mut gif = None;
+    let mut palette = default_palette();
+    let mut scale = 4;
+    let mut history_csv = false;
+    let mut map = false;
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+        let mut value = || flags.next().expect(usage);
+        match flag.as_str() {
+            "--threshold" => {
+                let threshold = value();
+                rule.threshold = threshold
+                    .parse()
+                    .unwrap_or_else(|_| panic!("Bad threshold: {threshold}"));
+            }
+            "--neighbourhood" => {
+                rule.neighbourhood =
+                    Neighbourhood::try_from(value().as_str()).unwrap_or_else(|e| panic!("{e}"));
+            }
+            "--occupied" => {
+                let occupied = value();
+                let mut chars = occupied.chars();
+                let (Some(c), None) = (chars.next(), chars.next()) else {
+                    panic!("--occupied takes one character, got: {occupied}");
+                };
+                rule.occupied = c;
+            }
+            "--step" => step = true,
+            "--delay" => {
+                let millis = value();
+                let millis = millis
+                    .parse()
+                    .unwrap_or_else(|_| panic!("Bad delay: {millis}"));
+                delay = Duration::from_millis(millis);
+            }
+            "--gif" => gif = Some(value()),
+            "--palette" => {
+                palette = Palette::try_from(value().as_str()).unwrap_or_else(|e| panic!("{e}"));
+            }
+            "--scale" => {
+                let px = value();
+                scale = px.parse().unwrap_or_else(|_| panic!("Bad scale: {px}"));
+            }
+            "--history" => history_csv = true,
+            "--map" => map = true,
+            _ => panic!("{usage}"),
+        }
+    }
+
+    let grid = Grid::new_from_file(file_name);
+    let history = rule.removal_history(&rule.papers(&grid));
+    if history_csv || map {
+        if history_csv {
+            print!("{}", history.to_csv());
+        }
+        if map {
+            println!("{}", history.render(&grid));
+            let sizes: Vec<String> = history.sizes().iter().map(usize::to_string).collect();
+            println!("Removed per round: {}", sizes.join(", "));
+        }
+        return;
+    }
+    let frames = history.frames(&grid);
+    match gif {
+        Some(path) => {
+            GifWriter::new(palette.with_default(rule.occupied, [240, 230, 200]))
+                .scale(scale)
+                .delay(delay)
+                .save(path, &frames)
+                .expect("Couldn't write the GIF");
+            println!("Saved {} frames to {path}", frames.len());
+        }
+        None => {
+            Animator::new(std::io::stdout())
+                .delay(delay)
+                .paused(step)
+                .controls(stdin_controls())
+                .play(frames)
+                .expect("Couldn't write to the terminal");
         }
-        sum += accessible_papers.len();
-        remove_papers(&mut papers, &accessible_papers);
     }
-    sum
 }
 
 /// Main function / code entry point.
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        run_query(&args);
+        return;
+    }
     println!("Sum for example1: {}", part1("example1.txt"));
     println!("Sum for input: {}", part1("input.txt"));
     println!("Sum for example1: {}", part2("example1.txt"));
@@ -96,4 +412,143 @@ mod tests {
         assert!(papers.contains(&Coord::new(8, 9)));
         assert!(!papers.contains(&Coord::new(0, 0)));
     }
+
+    /// The puzzle's example.
+    const EXAMPLE: &str = "\
+..@@.@@@@.
+@@@.@.@.@@
+@@@@@.@.@@
+@.@@@@..@.
+@@.@@@@.@@
+.@@@@@@@.@
+.@.@.@.@@@
+@.@@@.@@@@
+.@@@@@@@@.
+@.@.@@@.@.";
+
+    #[test]
+    fn test_removal_history() {
+        let grid = Grid::new_from_string(&EXAMPLE.to_string());
+        let history = Rule::default().removal_history(&get_papers(&grid));
+        assert_eq!(history.sizes(), vec![13, 12, 7, 5, 2, 1, 1, 1, 1]);
+        assert_eq!(history.total(), 43);
+        assert_eq!(history.round_of(&Coord::new(2, 0)), Some(1));
+        assert_eq!(history.round_of(&Coord::new(0, 0)), None);
+        let csv = history.to_csv();
+        assert_eq!(csv.lines().count(), 44);
+        assert!(csv.starts_with("round,x,y\n1,2,0\n1,3,0\n1,5,0\n"));
+        let map = history.render(&grid);
+        assert!(map.starts_with("..11.1121.\n134.2.2.32\n"));
+        assert_eq!(map.matches('@').count(), get_papers(&grid).len() - 43);
+    }
+
+    #[test]
+    fn test_removal_frames() {
+        let grid = Grid::new_from_string(&EXAMPLE.to_string());
+        let frames = Rule::default()
+            .removal_history(&get_papers(&grid))
+            .frames(&grid);
+        assert_eq!(frames.len(), 10);
+        assert_eq!(frames[0].grid, grid);
+        assert_eq!(frames[0].highlights.len(), 13);
+        assert_eq!(frames[0].caption, "Round 1: removing 13");
+        let left = get_papers(&frames[9].grid).len();
+        assert_eq!(left, get_papers(&grid).len() - 43);
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let grid = Grid::new_from_string(&"###\n###\n###".to_string());
+        let four = Rule {
+            threshold: 3,
+            neighbourhood: Neighbourhood::Four,
+            occupied: '#',
+        };
+        let papers = four.papers(&grid);
+        assert_eq!(papers.len(), 9);
+        // Corners have 2 neighbours, then the edges drop to 1, then the middle.
+        assert_eq!(four.accessible(&papers), 4);
+        assert_eq!(four.removal_history(&papers).sizes(), vec![4, 4, 1]);
+
+        // Only the cell to the east counts, so the east column goes first and
+        // the rest peel off west one column a round.
+        let east = Rule {
+            threshold: 1,
+            neighbourhood: Neighbourhood::try_from("1,0").unwrap(),
+            occupied: '#',
+        };
+        let history = east.removal_history(&papers);
+        assert_eq!(history.sizes(), vec![3, 3, 3]);
+        assert_eq!(history.round_of(&Coord::new(2, 1)), Some(1));
+        assert_eq!(history.round_of(&Coord::new(0, 1)), Some(3));
+
+        let nothing = Rule {
+            threshold: 0,
+            ..east
+        };
+        assert_eq!(nothing.removal_history(&papers), History::default());
+    }
+
+    #[test]
+    fn test_neighbourhood_try_from() {
+        assert_eq!(Neighbourhood::try_from("8"), Ok(Neighbourhood::Eight));
+        assert_eq!(
+            Neighbourhood::try_from("0,-1; 2,0;0,-1"),
+            Ok(Neighbourhood::Stencil(vec![
+                Coord::new(0, -1),
+                Coord::new(2, 0)
+            ]))
+        );
+        assert!(Neighbourhood::try_from("0,0").is_err());
+        assert!(Neighbourhood::try_from("1;2").is_err());
+    }
+
+    /// The old way of finding the rounds, checking every paper every round.
+    fn removal_rounds_by_scanning(papers: &HashSet<Coord>) -> Vec<HashSet<Coord>> {
+        let mut papers = papers.clone();
+        let mut rounds = Vec::new();
+        loop {
+            let accessible_papers: HashSet<Coord> = papers
+                .iter()
+                .filter(|x| x.get_surrounding_coords().intersection(&papers).count() < 4)
+                .copied()
+                .collect();
+            if accessible_papers.is_empty() {
+                break;
+            }
+            for paper in &accessible_papers {
+                papers.remove(paper);
+            }
+            rounds.push(accessible_papers);
+        }
+        rounds
+    }
+
+    #[test]
+    fn test_worklist_matches_scanning() {
+        let grid = Grid::new_from_string(&EXAMPLE.to_string());
+        let papers = get_papers(&grid);
+        let rule = Rule::default();
+        assert_eq!(
+            rule.removal_history(&papers).rounds,
+            removal_rounds_by_scanning(&papers)
+        );
+
+        // A bigger, denser pile made up from a simple generator.
+        let mut state: u64 = 12345;
+        let mut papers = HashSet::new();
+        for y in 0..60 {
+            for x in 0..60 {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                if state >> 61 != 0 {
+                    papers.insert(Coord::new(x, y));
+                }
+            }
+        }
+        let rounds = rule.removal_history(&papers).rounds;
+        assert!(rounds.len() > 3);
+        assert_eq!(rounds, removal_rounds_by_scanning(&papers));
+    }
 }
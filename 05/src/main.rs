@@ -2,30 +2,86 @@
 
 use std::{collections::VecDeque, ops::RangeInclusive};
 
+use utilities::parse::{
+    self, blank_line, integer, lines, map, opt, pair, separated_pair, tag, terminated,
+};
+
 /// Parse the incoming file to Vec of ranges and Vec of IDs.
 fn parse_text(string: &String) -> (VecDeque<RangeInclusive<u64>>, Vec<u64>) {
+    let range = map(
+        separated_pair(integer(), tag("-"), integer()),
+        |(start, end)| start..=end,
+    );
+    // The IDs after the blank line are optional, so a list of ranges on its
+    // own parses too.
+    let database = pair(
+        terminated(lines(range), opt(blank_line())),
+        lines(integer()),
+    );
+    let (ranges, ids) = parse::all(database, string).unwrap_or_else(|error| panic!("{error}"));
     // Using VecDeque for its rotate ability on part 2.
-    let mut ranges: VecDeque<RangeInclusive<u64>> = VecDeque::new();
-    let mut ids: Vec<u64> = Vec::new();
-
-    for line in string.lines() {
-        // Range
-        if line.trim().contains('-') {
-            let (start_raw, end_raw) = line.split_once('-').expect("Failed to split id_range.");
-            let start = u64::from_str_radix(start_raw, 10)
-                .expect(&format!("Failed to convert {start_raw} to u64."));
-            let end = u64::from_str_radix(end_raw, 10)
-                .expect(&format!("Failed to convert {end_raw} to u64."));
-            ranges.push_back(RangeInclusive::new(start, end));
-        // Ingredient ID if not the empty line.
-        } else if line.trim().len() > 0 {
-            ids.push(
-                u64::from_str_radix(line.trim(), 10)
-                    .expect(&format!("Failed to convert {line} to u64.")),
-            );
+    (ranges.into(), ids)
+}
+
+/// Struct representing the database of fresh ingredient ID ranges.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct FreshRanges {
+    /// Ranges in the order they appear in the database.
+    ranges: Vec<RangeInclusive<u64>>,
+}
+
+impl FreshRanges {
+    /// Make a new FreshRanges from the parsed ranges.
+    fn new(ranges: impl IntoIterator<Item = RangeInclusive<u64>>) -> Self {
+        Self {
+            ranges: ranges.into_iter().collect(),
+        }
+    }
+
+    /// Returns every range from the database that contains the id.
+    fn covering(&self, id: u64) -> Vec<&RangeInclusive<u64>> {
+        self.ranges
+            .iter()
+            .filter(|range| range.contains(&id))
+            .collect()
+    }
+
+    /// Returns the ranges merged together, sorted by start. Ranges that overlap
+    /// or touch end to end (e.g. 3-5 and 6-8) become a single range.
+    fn merged(&self) -> Vec<RangeInclusive<u64>> {
+        let mut sorted = self.ranges.clone();
+        sorted.sort_by_key(|range| *range.start());
+
+        let mut ret: Vec<RangeInclusive<u64>> = Vec::with_capacity(sorted.len());
+        for range in sorted {
+            match ret.last_mut() {
+                Some(last) if *range.start() <= last.end().saturating_add(1) => {
+                    if range.end() > last.end() {
+                        *last = RangeInclusive::new(*last.start(), *range.end());
+                    }
+                }
+                _ => ret.push(range),
+            }
         }
+        ret
+    }
+
+    /// Returns the ids not covered by any range between the smallest and largest
+    /// id in the database.
+    fn gaps(&self) -> Vec<RangeInclusive<u64>> {
+        self.merged()
+            .windows(2)
+            .map(|pair| RangeInclusive::new(pair[0].end() + 1, pair[1].start() - 1))
+            .collect()
+    }
+
+    /// Write the merged ranges back out in the same `a-b` format as the input.
+    fn to_database_string(&self) -> String {
+        self.merged()
+            .iter()
+            .map(|range| format!("{}-{}\n", range.start(), range.end()))
+            .collect()
     }
-    (ranges, ids)
 }
 
 /// Find ids not in the collection of ranges.
@@ -151,8 +207,57 @@ fn part2(file_name: &str) -> usize {
     sum
 }
 
+/// Load the ranges from the file into a FreshRanges database.
+fn load_fresh_ranges(file_name: &str) -> FreshRanges {
+    let file_contents = std::fs::read_to_string(file_name).expect("Couldn't open file");
+    let (ranges, _) = parse_text(&file_contents);
+    FreshRanges::new(ranges)
+}
+
+/// Run one of the range database queries. Returns the text to print.
+/// - `covering <file> <id>`: ranges that contain the id.
+/// - `gaps <file>`: uncovered ids between the smallest and largest fresh id.
+/// - `merged <file>`: merged ranges in the input's `a-b` format.
+fn run_query(args: &[String]) -> String {
+    let usage = "Usage: day05 [covering <file> <id> | gaps <file> | merged <file>]";
+    let (command, file_name) = match args {
+        [command, file_name, ..] => (command.as_str(), file_name.as_str()),
+        _ => panic!("{usage}"),
+    };
+    let database = load_fresh_ranges(file_name);
+
+    match command {
+        "covering" => {
+            let id_raw = args.get(2).expect(usage);
+            let id: u64 = id_raw
+                .parse()
+                .unwrap_or_else(|_| panic!("Failed to convert {id_raw} to u64."));
+            database
+                .covering(id)
+                .iter()
+                .map(|range| format!("{}-{}\n", range.start(), range.end()))
+                .collect()
+        }
+        "gaps" => database
+            .gaps()
+            .iter()
+            .map(|range| format!("{}-{}\n", range.start(), range.end()))
+            .collect(),
+        "merged" => database.to_database_string(),
+        _ => panic!("{usage}"),
+    }
+}
+
 /// Main function / code entry point.
 fn main() {
+    // Any arguments means we're running a range database query instead of the
+    // puzzle answers.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        print!("{}", run_query(&args));
+        return;
+    }
+
     println!("Sum for example1: {}", part1("example1.txt"));
     println!("Sum for input: {}", part1("input.txt"));
     println!("Sum for example1 part2: {}", part2("example1.txt"));
@@ -197,6 +302,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_fresh_ranges_covering() {
+        let database = FreshRanges::new([3..=5, 10..=14, 16..=20, 12..=18]);
+        assert_eq!(database.covering(1), Vec::<&RangeInclusive<u64>>::new());
+        assert_eq!(database.covering(5), vec![&(3..=5)]);
+        assert_eq!(database.covering(13), vec![&(10..=14), &(12..=18)]);
+        assert_eq!(database.covering(17), vec![&(16..=20), &(12..=18)]);
+    }
+
+    #[test]
+    fn test_fresh_ranges_merged() {
+        let database = FreshRanges::new([3..=5, 10..=14, 16..=20, 12..=18]);
+        assert_eq!(database.merged(), vec![3..=5, 10..=20]);
+        // Touching ranges merge as well.
+        let database = FreshRanges::new([6..=8, 3..=5]);
+        assert_eq!(database.merged(), vec![3..=8]);
+        // Contained ranges don't shrink the merged range.
+        let database = FreshRanges::new([1..=10, 2..=3]);
+        assert_eq!(database.merged(), vec![1..=10]);
+    }
+
+    #[test]
+    fn test_fresh_ranges_gaps() {
+        let database = FreshRanges::new([3..=5, 10..=14, 16..=20, 12..=18, 22..=22]);
+        assert_eq!(database.gaps(), vec![6..=9, 21..=21]);
+        assert_eq!(FreshRanges::new([1..=u64::MAX]).gaps(), vec![]);
+        assert_eq!(FreshRanges::default().gaps(), vec![]);
+    }
+
+    #[test]
+    fn test_fresh_ranges_to_database_string() {
+        let database = FreshRanges::new([3..=5, 10..=14, 16..=20, 12..=18]);
+        assert_eq!(database.to_database_string(), "3-5\n10-20\n");
+        // Round trip through parse_text.
+        let (ranges, _) = parse_text(&database.to_database_string());
+        assert_eq!(FreshRanges::new(ranges), FreshRanges::new([3..=5, 10..=20]));
+    }
+
+    #[test]
+    fn test_parse_text_line_endings() {
+        let expected = (VecDeque::from([3..=5, 10..=14]), vec![1, 5]);
+        assert_eq!(
+            parse_text(&"3-5\r\n10-14\r\n\r\n1\r\n5\r\n".to_string()),
+            expected
+        );
+        assert_eq!(parse_text(&"3-5\n10-14\n \n1\n5\n".to_string()), expected);
+    }
+
     #[test]
     fn test_merge_ranges() {
         assert_eq!(
@@ -1,26 +1,27 @@
 //! Day 06: Trash Compactor
 
-use std::collections::HashMap;
-use utilities::{coord::Coord, grid::Grid};
+use std::fmt;
+use std::num::NonZeroU64;
+use std::ops::Range;
 
 /// Enum that represents the operation to take place on a column.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 enum Operation {
     Add,
     Multiply,
+    /// The first number minus all the others.
+    Subtract,
+    Min,
+    Max,
+    /// Digits of the numbers joined in order, so 12 and 34 gives 1234.
+    Concat,
+    /// Sum kept modulo the value.
+    AddMod(NonZeroU64),
+    /// Product kept modulo the value.
+    MultiplyMod(NonZeroU64),
     NoOp,
 }
 
-impl From<&str> for Operation {
-    /// Returns an operation type from a string.
-    fn from(value: &str) -> Self {
-        match value {
-            "+" => Self::Add,
-            "*" => Self::Multiply,
-            _ => panic!("Operation::from_str got unexpected: {}", value),
-        }
-    }
-}
 impl Default for Operation {
     /// Returns the default of no operation (NoOp)
     fn default() -> Self {
@@ -28,6 +29,125 @@ impl Default for Operation {
     }
 }
 
+/// Why a column couldn't be worked out.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum EvalError {
+    /// The result doesn't fit in a u128 (or went below zero for Subtract).
+    Overflow,
+    /// The operation needs at least one number.
+    Empty,
+    /// The column never got an operator.
+    NoOperation,
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Overflow => write!(f, "overflowed"),
+            Self::Empty => write!(f, "has no numbers"),
+            Self::NoOperation => write!(f, "has no operator"),
+        }
+    }
+}
+
+impl Operation {
+    /// Apply the operation to the numbers with checked arithmetic.
+    fn apply(self, numbers: &[u64]) -> Result<u128, EvalError> {
+        let mut numbers = numbers.iter().map(|&x| u128::from(x));
+        match self {
+            Self::Add => numbers.try_fold(0u128, |acc, x| acc.checked_add(x)),
+            Self::Multiply => numbers.try_fold(1u128, |acc, x| acc.checked_mul(x)),
+            // Both sides are below the modulus, which fits in a u64, so neither
+            // the sum nor the product can overflow a u128.
+            Self::AddMod(modulus) => {
+                let modulus = u128::from(modulus.get());
+                Some(numbers.fold(0, |acc, x| (acc + x % modulus) % modulus))
+            }
+            Self::MultiplyMod(modulus) => {
+                let modulus = u128::from(modulus.get());
+                Some(numbers.fold(1 % modulus, |acc, x| (acc * (x % modulus)) % modulus))
+            }
+            Self::NoOp => return Err(EvalError::NoOperation),
+            _ => {
+                let first = numbers.next().ok_or(EvalError::Empty)?;
+                numbers.try_fold(first, |acc, x| match self {
+                    Self::Subtract => acc.checked_sub(x),
+                    Self::Min => Some(acc.min(x)),
+                    Self::Max => Some(acc.max(x)),
+                    Self::Concat => {
+                        let shift = 10u128.checked_pow(x.checked_ilog10().unwrap_or(0) + 1)?;
+                        acc.checked_mul(shift)?.checked_add(x)
+                    }
+                    _ => unreachable!(),
+                })
+            }
+        }
+        .ok_or(EvalError::Overflow)
+    }
+}
+
+/// Maps operator symbols on the worksheet to operations. New operators can be
+/// added with `register`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Registry {
+    operators: Vec<(u8, Operation)>,
+}
+
+impl Default for Registry {
+    /// The puzzle's `+` and `*`, plus `-`, `<` (min), `>` (max) and `|` (concat).
+    fn default() -> Self {
+        let mut registry = Self {
+            operators: Vec::new(),
+        };
+        registry.register(b'+', Operation::Add);
+        registry.register(b'*', Operation::Multiply);
+        registry.register(b'-', Operation::Subtract);
+        registry.register(b'<', Operation::Min);
+        registry.register(b'>', Operation::Max);
+        registry.register(b'|', Operation::Concat);
+        registry
+    }
+}
+
+impl Registry {
+    /// Default registry where `+` and `*` work modulo `modulus`.
+    fn modular(modulus: NonZeroU64) -> Self {
+        let mut registry = Self::default();
+        registry.register(b'+', Operation::AddMod(modulus));
+        registry.register(b'*', Operation::MultiplyMod(modulus));
+        registry
+    }
+
+    /// Add an operator, replacing whatever the symbol meant before.
+    fn register(&mut self, symbol: u8, operation: Operation) {
+        assert!(
+            !symbol.is_ascii_digit() && symbol != b' ',
+            "Registry::register can't use {:?} as an operator",
+            char::from(symbol)
+        );
+        match self.operators.iter_mut().find(|(s, _)| *s == symbol) {
+            Some(entry) => entry.1 = operation,
+            None => self.operators.push((symbol, operation)),
+        }
+    }
+
+    /// Returns the symbol an operation is written with, if it has one.
+    fn symbol(&self, operation: Operation) -> Option<char> {
+        self.operators
+            .iter()
+            .find(|(_, op)| *op == operation)
+            .map(|&(symbol, _)| char::from(symbol))
+    }
+
+    /// Returns the operation for a byte of the worksheet.
+    fn get(&self, symbol: u8) -> Option<Operation> {
+        self.operators
+            .iter()
+            .find(|(s, _)| *s == symbol)
+            .map(|&(_, operation)| operation)
+    }
+}
+
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
 struct Column {
     numbers: Vec<u64>,
@@ -35,146 +155,228 @@ struct Column {
 }
 
 impl Column {
-    /// Make a new Column from Vec(&str).
-    fn new_from_raw(entry: Vec<&str>) -> Self {
-        let mut ret = Self::default();
-        for number in entry {
-            if number == "+" || number == "*" {
-                ret.operation = Operation::from(number);
-                continue;
-            };
-            ret.numbers.push(
-                number
-                    .parse()
-                    .expect(&format!("Failed to parse: {}", number)),
-            );
-        }
-        ret
+    /// Get result of the Column operation.
+    fn result(&self) -> Result<u128, EvalError> {
+        self.operation.apply(&self.numbers)
     }
-}
 
-/// Get result of the Column operation.
-fn get_result(column: Column) -> u64 {
-    match column.operation {
-        Operation::Add => column.numbers.into_iter().sum(),
-        Operation::Multiply => column.numbers.into_iter().product(),
-        _ => panic!("get_result has no operation."),
+    /// Write the column out as a sum, like `123 * 45 * 6 = 33210`.
+    fn describe(&self, registry: &Registry) -> String {
+        let symbol = registry.symbol(self.operation).unwrap_or('?');
+        let numbers: Vec<String> = self.numbers.iter().map(u64::to_string).collect();
+        let result = match self.result() {
+            Ok(result) => result.to_string(),
+            Err(error) => error.to_string(),
+        };
+        format!("{} = {result}", numbers.join(&format!(" {symbol} ")))
     }
 }
 
-/// Parse the incoming file to HashMap of column#,Column.
-fn parse_text(string: &String) -> HashMap<usize, Column> {
-    let mut columns_raw: HashMap<usize, Vec<&str>> = HashMap::new();
-    let mut columns: HashMap<usize, Column> = HashMap::new();
+/// A problem that couldn't be worked out, by its index on the worksheet.
+/// Displayed 1-indexed, like the problem listing.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct ProblemError {
+    problem: usize,
+    error: EvalError,
+}
 
-    for line in string.lines() {
-        for (column, item) in line.split_whitespace().enumerate() {
-            columns_raw
-                .entry(column)
-                .and_modify(|x| x.push(item))
-                .or_insert_with(|| vec![item]);
-        }
+impl fmt::Display for ProblemError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "problem {} {}", self.problem + 1, self.error)
     }
+}
 
-    for (column, items) in columns_raw {
-        columns.insert(column, Column::new_from_raw(items));
-    }
+/// Sum the results of the given columns, stopping at the first problem that
+/// can't be worked out or that pushes the total past u128.
+fn sum_results<'a>(columns: impl IntoIterator<Item = &'a Column>) -> Result<u128, ProblemError> {
     columns
+        .into_iter()
+        .enumerate()
+        .try_fold(0u128, |total, (problem, column)| {
+            let result = column
+                .result()
+                .map_err(|error| ProblemError { problem, error })?;
+            total.checked_add(result).ok_or(ProblemError {
+                problem,
+                error: EvalError::Overflow,
+            })
+        })
+}
+
+/// One problem from the worksheet, read both ways.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+struct Problem {
+    /// Columns of the worksheet the problem takes up, 0-indexed.
+    span: Range<usize>,
+    /// Each row of digits is a number. (Part 1)
+    rows: Column,
+    /// Each column of digits is a number read top to bottom. Columns are in
+    /// right-to-left order. (Part 2)
+    columns: Column,
 }
 
-/// Parse the incoming file to HashMap of column#,Column.
-fn parse_text2(string: &String) -> HashMap<usize, Column> {
-    let mut rows_raw: HashMap<usize, Vec<&str>> = HashMap::new();
-    let mut rows: HashMap<usize, Column> = HashMap::new();
-    let mut column_count: usize = 0;
+/// Parse the worksheet straight from the bytes in one pass, going column by
+/// column. Problems are split on columns that are all spaces, the operator is
+/// on the last line, and every digit adds to both its row's number and its
+/// column's number. Problems come back in worksheet order, left to right.
+fn parse_worksheet(string: &str, registry: &Registry) -> Vec<Problem> {
+    let lines: Vec<&[u8]> = string
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::as_bytes)
+        .collect();
+    let Some((operator_line, number_lines)) = lines.split_last() else {
+        return Vec::new();
+    };
+    let width = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+    // Lines can be ragged, so anything past the end of a line is a space.
+    let byte_at = |line: &[u8], x: usize| line.get(x).copied().unwrap_or(b' ');
+
+    let mut problems = Vec::new();
+    let mut problem = Problem::default();
+    let mut row_numbers: Vec<Option<u64>> = vec![None; number_lines.len()];
+    let mut start = None;
 
-    for line in string.lines() {
-        // Increment the "column" count each empty line.
-        if line.split_whitespace().count() == 0 {
-            column_count += 1;
-            continue;
+    // Go one column past the end so the last problem gets finished like the rest.
+    for x in 0..=width {
+        let mut column_number: Option<u64> = None;
+        for (row, line) in number_lines.iter().enumerate() {
+            let byte = byte_at(line, x);
+            if byte.is_ascii_digit() {
+                let digit = u64::from(byte - b'0');
+                let add_digit = |number: Option<u64>| {
+                    number
+                        .unwrap_or(0)
+                        .checked_mul(10)
+                        .and_then(|number| number.checked_add(digit))
+                        .unwrap_or_else(|| {
+                            panic!(
+                                "parse_worksheet got a number too big for a u64 at row {}, column {}",
+                                row + 1,
+                                x + 1
+                            )
+                        })
+                };
+                row_numbers[row] = Some(add_digit(row_numbers[row]));
+                column_number = Some(add_digit(column_number));
+            } else if byte != b' ' {
+                panic!("parse_worksheet got unexpected: {}", char::from(byte));
+            }
         }
 
-        for item in line.split_whitespace() {
-            rows_raw
-                .entry(column_count)
-                .and_modify(|x| x.push(item))
-                .or_insert_with(|| vec![item]);
+        let operator = byte_at(operator_line, x);
+        if operator != b' ' {
+            let operation = registry.get(operator).unwrap_or_else(|| {
+                panic!(
+                    "parse_worksheet got unknown operator: {}",
+                    char::from(operator)
+                )
+            });
+            problem.rows.operation = operation;
+            problem.columns.operation = operation;
+        }
+
+        match column_number {
+            Some(number) => problem.columns.numbers.push(number),
+            // All spaces, so the problem is done.
+            None if operator == b' ' => {
+                if let Some(start) = start.take() {
+                    problem.span = start..x;
+                    problem.rows.numbers =
+                        row_numbers.iter_mut().filter_map(Option::take).collect();
+                    problem.columns.numbers.reverse();
+                    problems.push(std::mem::take(&mut problem));
+                }
+                continue;
+            }
+            None => {}
         }
+        start.get_or_insert(x);
     }
+    problems
+}
 
-    for (row, items) in rows_raw {
-        rows.insert(row, Column::new_from_raw(items));
+/// List every problem in order with where it is on the worksheet and how it
+/// works out both ways. Problems and columns are 1-indexed.
+fn describe_problems(problems: &[Problem], registry: &Registry) -> String {
+    let mut output = String::new();
+    for (index, problem) in problems.iter().enumerate() {
+        output += &format!(
+            "problem {} (columns {}-{})\n  rows:    {}\n  columns: {}\n",
+            index + 1,
+            problem.span.start + 1,
+            problem.span.end,
+            problem.rows.describe(registry),
+            problem.columns.describe(registry)
+        );
     }
-    rows
+    output
 }
 
-/// For each column of numbers, perform the operation at the bottom. Sum all results.
-fn part1(file_name: &str) -> u64 {
+/// Parse a worksheet file with the given operators.
+fn load_worksheet(file_name: &str, registry: &Registry) -> Vec<Problem> {
     let file_contents = std::fs::read_to_string(file_name).expect("Couldn't open file");
-    let columns = parse_text(&file_contents);
-    let answer = columns.into_iter().map(|x| get_result(x.1)).sum();
-    answer
-}
-
-/// Rotate a grid counter-clockwise.
-/// So this doesn't really rotate, it sorta flips the 2d matrix, but it works for
-/// what I need...
-fn rotate_grid_ccw(grid: &Grid) -> Grid {
-    let mut new_grid = Grid::new();
-    for (&coord, &char_val) in grid.char_map.iter() {
-        let new_x = coord.y;
-        let new_y = coord.x;
-
-        let new_coord = Coord::new(new_x, new_y);
-        new_grid.char_map.insert(new_coord, char_val);
-    }
-    new_grid.max_x = grid.max_y;
-    new_grid.max_y = grid.max_x;
-
-    new_grid
-}
-
-/// Convert the grid back to a string so we can use it in the logic that worked
-/// for part1.
-fn grid_to_string(grid: &Grid) -> String {
-    let mut ret = String::new();
-    for y in 0..=grid.max_y {
-        for x in 0..=grid.max_x {
-            let character = *grid
-                .char_map
-                .get(&Coord::new(x, y))
-                .expect(&format!("Failed to find char at: {:?}", (x, y)));
-            // Need to add a space before the operators so the parse by split_whitespace
-            // works.
-            if character == '+' || character == '*' {
-                ret.push(' ');
-                ret.push(character);
-                ret.push(' ');
-            } else {
-                ret.push(character);
-            };
-        }
-        // Push newline for each y/row.
-        ret.push_str("\n");
-    }
+    parse_worksheet(&file_contents, registry)
+}
 
-    ret
+/// For each column of numbers, perform the operation at the bottom. Sum all results.
+fn part1(file_name: &str) -> u128 {
+    let problems = load_worksheet(file_name, &Registry::default());
+    sum_results(problems.iter().map(|x| &x.rows)).unwrap_or_else(|e| panic!("part1: {e}"))
 }
 
 /// For each column of numbers, perform the operation at the bottom. Sum all results.
 /// Read numbers right-to-left in each column.
-fn part2(file_name: &str) -> u64 {
-    let grid = Grid::new_from_file(file_name);
-    let new_grid = rotate_grid_ccw(&grid);
-    let new_string = grid_to_string(&new_grid);
-    let columns = parse_text2(&new_string);
-    let answer = columns.into_iter().map(|x| get_result(x.1)).sum();
-    answer
+fn part2(file_name: &str) -> u128 {
+    let problems = load_worksheet(file_name, &Registry::default());
+    sum_results(problems.iter().map(|x| &x.columns)).unwrap_or_else(|e| panic!("part2: {e}"))
+}
+
+/// Handle `day06 <file> [--mod N] [--list]`, printing both totals for the
+/// file. With `--mod`, `+` and `*` work modulo N. With `--list`, every problem
+/// is printed first.
+fn run_query(args: &[String]) {
+    let usage = "Usage: day06 <file> [--mod N] [--list]";
+    let (file_name, flags) = args.split_first().expect(usage);
+    let mut registry = Registry::default();
+    let mut list = false;
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "--mod" => {
+                let modulus = flags.next().expect(usage);
+                registry =
+                    Registry::modular(modulus.parse().unwrap_or_else(|_| {
+                        panic!("Bad modulus: {modulus}, it needs to be above 0")
+                    }));
+            }
+            "--list" => list = true,
+            _ => panic!("{usage}"),
+        }
+    }
+
+    let problems = load_worksheet(file_name, &registry);
+    if list {
+        print!("{}", describe_problems(&problems, &registry));
+    }
+    for (name, total) in [
+        ("rows", sum_results(problems.iter().map(|x| &x.rows))),
+        ("columns", sum_results(problems.iter().map(|x| &x.columns))),
+    ] {
+        match total {
+            Ok(total) => println!("Sum by {name}: {total}"),
+            Err(error) => println!("Sum by {name}: {error}"),
+        }
+    }
 }
 
 /// Main function / code entry point.
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        run_query(&args);
+        return;
+    }
     println!("Sum for example1: {}", part1("example1.txt"));
     println!("Sum for input: {}", part1("input.txt"));
     println!("Sum for example1 part2: {}", part2("example1.txt"));
@@ -206,4 +408,188 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2("input.txt"), 11602774058280);
     }
+
+    /// Same worksheet as the puzzle's example.
+    const EXAMPLE: &str = "123 328  51 64 \n 45 64  387 23 \n  6 98  215 314\n*   +   *   +  \n";
+
+    #[test]
+    fn test_parse_worksheet() {
+        let problems = parse_worksheet(EXAMPLE, &Registry::default());
+        assert_eq!(problems.len(), 4);
+        assert_eq!(
+            problems[0],
+            Problem {
+                span: 0..3,
+                rows: Column {
+                    numbers: vec![123, 45, 6],
+                    operation: Operation::Multiply,
+                },
+                columns: Column {
+                    numbers: vec![356, 24, 1],
+                    operation: Operation::Multiply,
+                },
+            }
+        );
+        assert_eq!(problems[3].rows.numbers, vec![64, 23, 314]);
+        assert_eq!(problems[3].columns.numbers, vec![4, 431, 623]);
+        assert_eq!(problems[3].rows.operation, Operation::Add);
+    }
+
+    #[test]
+    fn test_parse_worksheet_results() {
+        let problems = parse_worksheet(EXAMPLE, &Registry::default());
+        assert_eq!(sum_results(problems.iter().map(|x| &x.rows)), Ok(4277556));
+        assert_eq!(
+            sum_results(problems.iter().map(|x| &x.columns)),
+            Ok(3263827)
+        );
+    }
+
+    /// Trailing spaces trimmed off the lines shouldn't matter.
+    #[test]
+    fn test_parse_worksheet_ragged() {
+        let trimmed: String = EXAMPLE
+            .lines()
+            .map(|line| line.trim_end().to_string() + "\n")
+            .collect();
+        assert_eq!(
+            parse_worksheet(&trimmed, &Registry::default()),
+            parse_worksheet(EXAMPLE, &Registry::default())
+        );
+        assert_eq!(parse_worksheet("", &Registry::default()), vec![]);
+    }
+
+    fn modulus(value: u64) -> NonZeroU64 {
+        NonZeroU64::new(value).unwrap()
+    }
+
+    fn column(numbers: &[u64], operation: Operation) -> Column {
+        Column {
+            numbers: numbers.to_vec(),
+            operation,
+        }
+    }
+
+    #[test]
+    fn test_operations() {
+        let numbers = [12, 7, 340];
+        assert_eq!(column(&numbers, Operation::Add).result(), Ok(359));
+        assert_eq!(column(&numbers, Operation::Multiply).result(), Ok(28560));
+        assert_eq!(column(&[340, 12, 7], Operation::Subtract).result(), Ok(321));
+        assert_eq!(column(&numbers, Operation::Min).result(), Ok(7));
+        assert_eq!(column(&numbers, Operation::Max).result(), Ok(340));
+        assert_eq!(column(&numbers, Operation::Concat).result(), Ok(127340));
+        assert_eq!(column(&[5, 0], Operation::Concat).result(), Ok(50));
+        assert_eq!(
+            column(&numbers, Operation::AddMod(modulus(100))).result(),
+            Ok(59)
+        );
+        assert_eq!(
+            column(&numbers, Operation::MultiplyMod(modulus(1000))).result(),
+            Ok(560)
+        );
+        // Products of numbers just under a modulus this big would overflow a
+        // u64, but not the u128 they're worked out in.
+        let big = u64::MAX - 1;
+        assert_eq!(
+            column(&[big, big], Operation::MultiplyMod(modulus(u64::MAX))).result(),
+            Ok(1)
+        );
+        assert_eq!(
+            column(&[big, big], Operation::AddMod(modulus(u64::MAX))).result(),
+            Ok(u128::from(big - 1))
+        );
+    }
+
+    #[test]
+    fn test_operation_errors() {
+        assert_eq!(
+            column(&[1, 2], Operation::Subtract).result(),
+            Err(EvalError::Overflow)
+        );
+        assert_eq!(column(&[], Operation::Max).result(), Err(EvalError::Empty));
+        assert_eq!(column(&[], Operation::Add).result(), Ok(0));
+        assert_eq!(
+            column(&[1], Operation::NoOp).result(),
+            Err(EvalError::NoOperation)
+        );
+        // Three u64::MAX multiply past u128.
+        assert_eq!(
+            column(&[u64::MAX; 3], Operation::Multiply).result(),
+            Err(EvalError::Overflow)
+        );
+        assert_eq!(
+            column(&[u64::MAX; 2], Operation::Multiply).result(),
+            Ok(u128::from(u64::MAX) * u128::from(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn test_sum_results_reports_problem() {
+        let columns = [
+            column(&[1, 2], Operation::Add),
+            column(&[u64::MAX; 3], Operation::Multiply),
+        ];
+        assert_eq!(
+            sum_results(&columns),
+            Err(ProblemError {
+                problem: 1,
+                error: EvalError::Overflow
+            })
+        );
+        assert_eq!(sum_results(&columns[..1]), Ok(3));
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = Registry::default();
+        registry.register(b'%', Operation::AddMod(modulus(7)));
+        let problems = parse_worksheet("10 4\n 3 9\n-  %\n", &registry);
+        assert_eq!(sum_results(problems.iter().map(|x| &x.rows)), Ok(7 + 6));
+        assert_eq!(registry.get(b'?'), None);
+
+        // Each problem is worked out modulo 1000, then the results are added.
+        let problems = parse_worksheet(EXAMPLE, &Registry::modular(modulus(1000)));
+        assert_eq!(
+            sum_results(problems.iter().map(|x| &x.rows)),
+            Ok(210 + 490 + 455 + 401)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "too big for a u64 at row 1, column 21")]
+    fn test_parse_worksheet_long_row() {
+        parse_worksheet("123456789012345678901 2\n+\n", &Registry::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "too big for a u64 at row 20, column 2")]
+    fn test_parse_worksheet_tall_column() {
+        parse_worksheet(&format!("{}*\n", " 9\n".repeat(21)), &Registry::default());
+    }
+
+    #[test]
+    fn test_parse_worksheet_spans() {
+        let problems = parse_worksheet(EXAMPLE, &Registry::default());
+        let spans: Vec<Range<usize>> = problems.iter().map(|x| x.span.clone()).collect();
+        assert_eq!(spans, vec![0..3, 4..7, 8..11, 12..15]);
+        // Wider gaps and an operator sticking out past the numbers.
+        let problems = parse_worksheet(" 1  23\n 4   5\n + *  \n", &Registry::default());
+        let spans: Vec<Range<usize>> = problems.iter().map(|x| x.span.clone()).collect();
+        assert_eq!(spans, vec![1..2, 3..6]);
+    }
+
+    #[test]
+    fn test_describe_problems() {
+        let registry = Registry::default();
+        let problems = parse_worksheet(EXAMPLE, &registry);
+        let listing = describe_problems(&problems[..2], &registry);
+        assert_eq!(
+            listing,
+            "problem 1 (columns 1-3)\n  rows:    123 * 45 * 6 = 33210\n  columns: 356 * 24 * 1 = 8544\n\
+             problem 2 (columns 5-7)\n  rows:    328 + 64 + 98 = 490\n  columns: 8 + 248 + 369 = 625\n"
+        );
+        let overflow = column(&[u64::MAX; 3], Operation::Multiply);
+        assert!(overflow.describe(&registry).ends_with("= overflowed"));
+    }
 }
@@ -1,128 +1,482 @@
 //! Day 07: Laboratories
 
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    time::Duration,
+};
 
 use utilities::{
-    coord::Coord,
-    grid::{Grid, iter_char_map_keys},
+    bigint::Count,
+    coord::{Coord, Direction},
+    gif::{GifWriter, Palette},
+    grid::Grid,
+    viz::{Animator, Frame, stdin_controls},
 };
 
-/// Check if the beam is above this coordinate. E.g., do we care about this coord?
-fn is_beam_above(coord: &Coord, grid: &Grid) -> bool {
-    if grid
-        .char_map
-        .get(&coord.north())
-        .is_some_and(|&x| x == '|' || x == 'S')
-    {
-        true
-    } else {
-        false
-    }
+/// What a beam does when it reaches a cell.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Tile {
+    /// Beam keeps going. Covers `.`, `|` and the `S` start.
+    Empty,
+    /// `^`: beam splits into two beams either side of it, both carrying on in
+    /// the same direction.
+    Splitter,
+    /// `/`: beam turns, east <-> north and west <-> south.
+    MirrorSlash,
+    /// `\`: beam turns, east <-> south and west <-> north.
+    MirrorBackslash,
+    /// `#`: beam stops.
+    Absorber,
 }
 
-/// Check if this Coord will split the beam.
-fn check_split(coord: &Coord, grid: &Grid) -> bool {
-    if grid.char_map.get(coord).is_some_and(|&x| x == '^') {
-        if is_beam_above(coord, grid) {
-            return true;
+impl TryFrom<char> for Tile {
+    type Error = &'static str;
+
+    /// Returns the tile for a character of the manifold.
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        match value {
+            '.' | '|' | 'S' => Ok(Self::Empty),
+            '^' => Ok(Self::Splitter),
+            '/' => Ok(Self::MirrorSlash),
+            '\\' => Ok(Self::MirrorBackslash),
+            '#' => Ok(Self::Absorber),
+            _ => Err("Tile must be one of . | S ^ / \\ #"),
         }
     }
-    false
 }
 
-/// Splits the beam at the given coordinate by placing '|' on left/right.
-fn split_beam(coord: &Coord, grid: &mut Grid) {
-    let left = coord.west();
-    let right = coord.east();
-    grid.char_map.entry(left).and_modify(|x| *x = '|');
-    grid.char_map.entry(right).and_modify(|x| *x = '|');
+/// A beam in a cell, heading in a direction.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct Beam {
+    coord: Coord,
+    direction: Direction,
 }
 
-/// Check if the beam should continue.
-fn check_continues(coord: &Coord, grid: &Grid) -> bool {
-    if grid
-        .char_map
-        .get(coord)
-        .is_some_and(|&x| x == '.' || x == '|')
-    {
-        if is_beam_above(coord, &grid) {
-            return true;
-        }
+impl Beam {
+    fn new(coord: Coord, direction: Direction) -> Self {
+        Self { coord, direction }
     }
-    false
 }
 
-/// Continue the beam through this coordinate.
-fn continue_beam(coord: Coord, grid: &mut Grid) {
-    grid.char_map.entry(coord).and_modify(|x| *x = '|');
+/// Everything the beams touched in a run of the engine.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct Trace {
+    /// Every beam state that happened.
+    beams: HashSet<Beam>,
+    /// Splitters that a beam hit.
+    splits: HashSet<Coord>,
 }
 
-/// Figure out how many times the beam splits.
-fn part1(file_name: &str) -> u64 {
-    let mut grid = Grid::new_from_file(file_name);
-    let mut splits = 0;
-    for coord in iter_char_map_keys(grid.max_x, grid.max_y) {
-        if !is_beam_above(&coord, &grid) {
-            continue;
+impl Trace {
+    /// Cells with a beam in them.
+    fn energized(&self) -> HashSet<Coord> {
+        self.beams.iter().map(|beam| beam.coord).collect()
+    }
+}
+
+/// Simulates beams through a manifold without touching the grid.
+#[derive(Clone, Debug)]
+struct BeamEngine<'a> {
+    grid: &'a Grid,
+    sources: Vec<Beam>,
+}
+
+impl<'a> BeamEngine<'a> {
+    /// Make an engine with a south heading beam from every `S` in the grid.
+    fn new(grid: &'a Grid) -> Self {
+        let mut starts: Vec<Coord> = grid
+            .char_map
+            .iter()
+            .filter(|&(_, &c)| c == 'S')
+            .map(|(&coord, _)| coord)
+            .collect();
+        starts.sort_by_key(|coord| (coord.y, coord.x));
+        Self {
+            grid,
+            sources: starts
+                .into_iter()
+                .map(|coord| Beam::new(coord, Direction::South))
+                .collect(),
         }
-        if check_continues(&coord, &grid) {
-            continue_beam(coord, &mut grid);
+    }
+
+    /// Add another beam entering at the coordinate.
+    fn with_source(mut self, coord: Coord, direction: Direction) -> Self {
+        self.sources.push(Beam::new(coord, direction));
+        self
+    }
+
+    /// Returns the tile at the coordinate, or None if it's off the grid.
+    fn tile(&self, coord: &Coord) -> Option<Tile> {
+        self.grid
+            .char_map
+            .get(coord)
+            .map(|&c| Tile::try_from(c).unwrap_or_else(|e| panic!("{e}, got {c:?} at {coord}")))
+    }
+
+    /// Where the beam goes after the cell it's in. Beams can leave the grid.
+    fn step(&self, beam: Beam, tile: Tile) -> Vec<Beam> {
+        let Beam { coord, direction } = beam;
+        let turn = |direction: Direction| vec![Beam::new(coord.step(direction), direction)];
+        match tile {
+            Tile::Empty => turn(direction),
+            Tile::Splitter => vec![
+                Beam::new(coord.step(direction.turn_left()), direction),
+                Beam::new(coord.step(direction.turn_right()), direction),
+            ],
+            Tile::MirrorSlash => match direction {
+                Direction::North | Direction::South => turn(direction.turn_right()),
+                Direction::East | Direction::West => turn(direction.turn_left()),
+            },
+            Tile::MirrorBackslash => match direction {
+                Direction::North | Direction::South => turn(direction.turn_left()),
+                Direction::East | Direction::West => turn(direction.turn_right()),
+            },
+            Tile::Absorber => Vec::new(),
         }
-        if check_split(&coord, &grid) {
-            split_beam(&coord, &mut grid);
-            splits += 1;
+    }
+
+    /// Follow every beam until it leaves the grid, is absorbed or repeats.
+    fn run(&self) -> Trace {
+        let mut trace = Trace::default();
+        let mut queue: Vec<Beam> = self.sources.clone();
+        while let Some(beam) = queue.pop() {
+            let Some(tile) = self.tile(&beam.coord) else {
+                continue;
+            };
+            if !trace.beams.insert(beam) {
+                continue;
+            }
+            if tile == Tile::Splitter {
+                trace.splits.insert(beam.coord);
+            }
+            queue.extend(self.step(beam, tile));
         }
+        trace
+    }
+
+    /// Follow the beams one step at a time. Returns the cells lit up for the
+    /// first time at each step, leaving out steps that light nothing new.
+    fn waves(&self) -> Vec<HashSet<Coord>> {
+        let mut seen: HashSet<Beam> = HashSet::new();
+        let mut lit: HashSet<Coord> = HashSet::new();
+        let mut waves = Vec::new();
+        let mut frontier = self.sources.clone();
+        while !frontier.is_empty() {
+            let mut wave = HashSet::new();
+            let mut next = Vec::new();
+            for beam in frontier {
+                let Some(tile) = self.tile(&beam.coord) else {
+                    continue;
+                };
+                if !seen.insert(beam) {
+                    continue;
+                }
+                if lit.insert(beam.coord) {
+                    wave.insert(beam.coord);
+                }
+                next.extend(self.step(beam, tile));
+            }
+            if !wave.is_empty() {
+                waves.push(wave);
+            }
+            frontier = next;
+        }
+        waves
+    }
+
+    /// Count the timelines, where each split makes a timeline for each side. A
+    /// timeline ends when its beam leaves the grid or is absorbed. Returns None
+    /// if beams can go round in a loop, since then there's no end to them.
+    fn timelines(&self) -> Option<Timelines> {
+        // Number of timelines from each beam state on. Worked out depth first
+        // with our own stack, as beams can be far longer than the call stack.
+        let mut leaving: HashMap<Beam, Count> = HashMap::new();
+        let mut in_progress: HashSet<Beam> = HashSet::new();
+        // Beam states in the grid, each one after everything it leads to.
+        let mut finished: Vec<Beam> = Vec::new();
+        let mut stack: Vec<(Beam, bool)> = self.sources.iter().map(|&b| (b, false)).collect();
+        while let Some((beam, expanded)) = stack.pop() {
+            if leaving.contains_key(&beam) {
+                continue;
+            }
+            let Some(tile) = self.tile(&beam.coord) else {
+                leaving.insert(beam, Count::from(1));
+                continue;
+            };
+            let next = self.step(beam, tile);
+            if expanded {
+                in_progress.remove(&beam);
+                let count = if next.is_empty() {
+                    Count::from(1)
+                } else {
+                    next.iter().map(|b| &leaving[b]).sum()
+                };
+                leaving.insert(beam, count);
+                finished.push(beam);
+                continue;
+            }
+            in_progress.insert(beam);
+            stack.push((beam, true));
+            for next_beam in next {
+                if in_progress.contains(&next_beam) {
+                    return None;
+                }
+                stack.push((next_beam, false));
+            }
+        }
+
+        // Going the other way, everything that leads to a state is done before
+        // it, so the timelines reaching it can be pushed on to what's next.
+        let mut arriving: HashMap<Beam, Count> = HashMap::new();
+        for source in &self.sources {
+            *arriving.entry(*source).or_default() += &Count::from(1);
+        }
+        for &beam in finished.iter().rev() {
+            let Some(count) = arriving.get(&beam).cloned() else {
+                continue;
+            };
+            let tile = self
+                .tile(&beam.coord)
+                .expect("Finished beams are in the grid");
+            for next_beam in self.step(beam, tile) {
+                *arriving.entry(next_beam).or_default() += &count;
+            }
+        }
+
+        let total = self.sources.iter().map(|b| &leaving[b]).sum();
+        Some(Timelines {
+            arriving,
+            leaving,
+            total,
+        })
+    }
+
+    /// Count the timelines. See `timelines`.
+    fn count_timelines(&self) -> Option<Count> {
+        self.timelines().map(|timelines| timelines.total)
+    }
+}
+
+/// How timelines spread over the beam states of a manifold.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct Timelines {
+    /// How many timelines reach each beam state.
+    arriving: HashMap<Beam, Count>,
+    /// How many timelines carry on from each beam state, counting how they split.
+    leaving: HashMap<Beam, Count>,
+    /// How many timelines there are in all.
+    total: Count,
+}
+
+impl Timelines {
+    /// Characters for the heatmap from few timelines to many.
+    const HEAT: &[u8] = b":-=+*#%@";
+
+    /// How many timelines pass through the cell. A timeline that goes through
+    /// the same cell in two directions counts twice.
+    fn through(&self, coord: &Coord) -> Count {
+        Direction::ALL
+            .into_iter()
+            .filter_map(|direction| {
+                let beam = Beam::new(*coord, direction);
+                Some(self.arriving.get(&beam)? * self.leaving.get(&beam)?)
+            })
+            .fold(Count::default(), |acc, x| &acc + &x)
+    }
+
+    /// Draw the grid with cells shaded by how many timelines go through them,
+    /// on a log scale from `:` to `@`. Cells that aren't empty keep their own
+    /// character and empty cells no timeline goes through stay `.`.
+    fn heatmap(&self, grid: &Grid) -> String {
+        let counts: HashMap<Coord, Count> = grid
+            .char_map
+            .keys()
+            .map(|coord| (*coord, self.through(coord)))
+            .collect();
+        let max_bits = counts.values().map(Count::bits).max().unwrap_or(0);
+        let mut lines = Vec::new();
+        for y in 0..=grid.max_y {
+            let mut line = String::new();
+            for x in 0..=grid.max_x {
+                let coord = Coord::new(x, y);
+                let Some(&c) = grid.char_map.get(&coord) else {
+                    line.push(' ');
+                    continue;
+                };
+                let bits = counts[&coord].bits();
+                if Tile::try_from(c) != Ok(Tile::Empty) || bits == 0 {
+                    line.push(if c == '|' { '.' } else { c });
+                } else {
+                    // The busiest cells get the last character.
+                    let top = Self::HEAT.len() as u64 - 1;
+                    let level = top - (max_bits - bits) * top / (max_bits - 1).max(1);
+                    line.push(char::from(Self::HEAT[level as usize]));
+                }
+            }
+            lines.push(line);
+        }
+        lines.join("\n")
     }
-    splits
+}
+
+/// A frame of the bare grid, then one for each step of the beams with the
+/// newly lit cells highlighted. Lit empty cells are drawn as `|`.
+fn beam_frames(engine: &BeamEngine) -> Vec<Frame> {
+    let mut grid = engine.grid.clone();
+    let mut frames = vec![Frame::new(grid.clone()).with_caption("Step 0")];
+    for (step, wave) in engine.waves().into_iter().enumerate() {
+        for coord in &wave {
+            grid.char_map.entry(*coord).and_modify(|c| {
+                if *c == '.' {
+                    *c = '|';
+                }
+            });
+        }
+        let caption = format!("Step {}: {} new cells lit", step + 1, wave.len());
+        frames.push(
+            Frame::new(grid.clone())
+                .with_highlights(wave)
+                .with_caption(&caption),
+        );
+    }
+    frames
+}
+
+/// Figure out how many times the beam splits.
+fn part1(file_name: &str) -> u64 {
+    let grid = Grid::new_from_file(file_name);
+    BeamEngine::new(&grid).run().splits.len() as u64
 }
 
 /// At each split, create two new "timelines" where each has the beam take a different
 /// path. Sum the amount of timelines.
 fn part2(file_name: &str) -> u64 {
-    let mut grid = Grid::new_from_file(file_name);
-    let mut summing_grid: HashMap<Coord, u64> = HashMap::new();
-
-    // Use the same splitting logic from part1, but keep a second grid of values.
-    // These values "waterfall" down and sum when they combine. The values represent
-    // how many possible paths led to that coordinate.
-    for coord in iter_char_map_keys(grid.max_x, grid.max_y) {
-        if !is_beam_above(&coord, &grid) {
-            continue;
-        }
-        if check_continues(&coord, &grid) {
-            continue_beam(coord, &mut grid);
-            let north_val = *summing_grid.get(&coord.north()).unwrap_or(&1);
-            summing_grid
-                .entry(coord)
-                .and_modify(|x| *x += north_val)
-                .or_insert(north_val);
-        }
-        if check_split(&coord, &grid) {
-            split_beam(&coord, &mut grid);
-            let north_val = *summing_grid.get(&coord.north()).unwrap_or(&1);
-            summing_grid
-                .entry(coord.east())
-                .and_modify(|x| *x += north_val)
-                .or_insert(north_val);
-            summing_grid
-                .entry(coord.west())
-                .and_modify(|x| *x += north_val)
-                .or_insert(north_val);
+    let grid = Grid::new_from_file(file_name);
+    BeamEngine::new(&grid)
+        .count_timelines()
+        .expect("Beams go round in a loop")
+        .to_u64()
+        .expect("Too many timelines for a u64, use `day07 <file>` to print them")
+}
+
+/// Parse an `x,y` coordinate from the command line.
+fn parse_coord(string: &str) -> Coord {
+    let (x, y) = string
+        .split_once(',')
+        .unwrap_or_else(|| panic!("Coordinates go x,y, got: {string}"));
+    Coord::new(
+        x.parse().unwrap_or_else(|_| panic!("Bad x: {x}")),
+        y.parse().unwrap_or_else(|_| panic!("Bad y: {y}")),
+    )
+}
+
+/// Colors for the GIF: yellow beams on black, with grey splitters.
+fn default_palette() -> Palette {
+    Palette::new([0, 0, 0])
+        .with('.', [0, 0, 0])
+        .with('|', [250, 210, 60])
+        .with('S', [80, 200, 120])
+        .with('^', [150, 150, 150])
+        .with('/', [90, 140, 255])
+        .with('\\', [90, 140, 255])
+        .with('#', [140, 90, 60])
+}
+
+/// Handle `day07 <file> [--heatmap] [--through x,y]... [--animate] [--step]
+/// [--delay ms] [--gif out.gif] [--palette spec] [--scale px]
+/// [x,y,direction]...`, adding a source for each `x,y,direction` on top of any
+/// `S` in the grid, and printing what the beams do. Timeline counts don't
+/// overflow, however big. `--animate` plays the beams spreading in the
+/// terminal first; `--step` and `--delay` imply it. `--gif` saves the beams
+/// spreading as a GIF.
+fn run_query(args: &[String]) {
+    let usage = "Usage: day07 <file> [--heatmap] [--through x,y]... [--animate] [--step] \
+                 [--delay ms] [--gif out.gif] [--palette spec] [--scale px] [x,y,direction]...";
+    let (file_name, flags) = args.split_first().expect(usage);
+    let grid = Grid::new_from_file(file_name);
+    let mut engine = BeamEngine::new(&grid);
+    let mut heatmap = false;
+    let mut cells = Vec::new();
+    let mut animate = false;
+    let mut step = false;
+    let mut delay = Duration::from_millis(100);
+    let mut gif = None;
+    let mut palette = default_palette();
+    let mut scale = 4;
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "--heatmap" => heatmap = true,
+            "--through" => cells.push(parse_coord(flags.next().expect(usage))),
+            "--animate" => animate = true,
+            "--step" => (animate, step) = (true, true),
+            "--delay" => {
+                let millis = flags.next().expect(usage);
+                let millis = millis
+                    .parse()
+                    .unwrap_or_else(|_| panic!("Bad delay: {millis}"));
+                (animate, delay) = (true, Duration::from_millis(millis));
+            }
+            "--gif" => gif = Some(flags.next().expect(usage)),
+            "--palette" => {
+                let spec = flags.next().expect(usage);
+                palette = Palette::try_from(spec.as_str()).unwrap_or_else(|e| panic!("{e}"));
+            }
+            "--scale" => {
+                let px = flags.next().expect(usage);
+                scale = px.parse().unwrap_or_else(|_| panic!("Bad scale: {px}"));
+            }
+            source => {
+                let (coord, direction) = source.rsplit_once(',').expect(usage);
+                let direction = Direction::try_from(direction).unwrap_or_else(|e| panic!("{e}"));
+                engine = engine.with_source(parse_coord(coord), direction);
+            }
         }
     }
 
-    // Sum the last row to get the final amount of timelines.
-    let mut sum = 0;
-    for x in 0..=grid.max_x {
-        let val = summing_grid.get(&Coord::new(x, grid.max_y)).unwrap_or(&0);
-        sum += val
+    if let Some(path) = gif {
+        let frames = beam_frames(&engine);
+        GifWriter::new(palette)
+            .scale(scale)
+            .delay(delay)
+            .save(path, &frames)
+            .expect("Couldn't write the GIF");
+        println!("Saved {} frames to {path}", frames.len());
+    }
+    if animate {
+        Animator::new(io::stdout())
+            .delay(delay)
+            .paused(step)
+            .controls(stdin_controls())
+            .play(beam_frames(&engine))
+            .expect("Couldn't write to the terminal");
     }
 
-    sum
+    let trace = engine.run();
+    println!("Splitters hit: {}", trace.splits.len());
+    println!("Cells energized: {}", trace.energized().len());
+    let Some(timelines) = engine.timelines() else {
+        println!("Timelines: beams loop forever");
+        return;
+    };
+    println!("Timelines: {}", timelines.total);
+    for cell in cells {
+        println!("Timelines through {cell}: {}", timelines.through(&cell));
+    }
+    if heatmap {
+        println!("{}", timelines.heatmap(&grid));
+    }
 }
 
 /// Main function / code entry point.
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        run_query(&args);
+        return;
+    }
     println!("Sum for example1: {}", part1("example1.txt"));
     println!("Sum for input: {}", part1("input.txt"));
     println!("Sum for example1 part2: {}", part2("example1.txt"));
@@ -154,4 +508,146 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2("input.txt"), 16937871060075);
     }
+
+    /// The puzzle's example manifold.
+    const EXAMPLE: &str = "\
+.......S.......
+...............
+.......^.......
+...............
+......^.^......
+...............
+.....^.^.^.....
+...............
+....^.^...^....
+...............
+...^.^...^.^...
+...............
+..^...^.....^..
+...............
+.^.^.^.^.^...^.
+...............";
+
+    #[test]
+    fn test_example_queries() {
+        let grid = Grid::new_from_string(&EXAMPLE.to_string());
+        let before = grid.clone();
+        let engine = BeamEngine::new(&grid);
+        assert_eq!(engine.run().splits.len(), 21);
+        assert_eq!(engine.count_timelines(), Some(Count::from(40)));
+        assert_eq!(grid, before);
+    }
+
+    #[test]
+    fn test_mirrors_and_absorbers() {
+        // East into `\` goes south, south into `\` goes east, then hits `#`.
+        let grid = Grid::new_from_string(&"\\..\n....\n\\..#".to_string());
+        let engine = BeamEngine {
+            grid: &grid,
+            sources: Vec::new(),
+        }
+        .with_source(Coord::new(0, 0), Direction::East);
+        let trace = engine.run();
+        let expected: HashSet<Coord> = [(0, 0), (0, 1), (0, 2), (1, 2), (2, 2), (3, 2)]
+            .into_iter()
+            .map(|(x, y)| Coord::new(x, y))
+            .collect();
+        assert_eq!(trace.energized(), expected);
+        assert!(trace.splits.is_empty());
+        assert_eq!(engine.count_timelines(), Some(Count::from(1)));
+    }
+
+    #[test]
+    fn test_sideways_split_and_multiple_sources() {
+        // Heading west into `^` splits north and south.
+        let grid = Grid::new_from_string(&".....\n.^...\n.....".to_string());
+        let engine = BeamEngine::new(&grid).with_source(Coord::new(4, 1), Direction::West);
+        let trace = engine.run();
+        assert_eq!(trace.splits, HashSet::from([Coord::new(1, 1)]));
+        assert!(
+            trace
+                .beams
+                .contains(&Beam::new(Coord::new(1, 0), Direction::West))
+        );
+        assert!(
+            trace
+                .beams
+                .contains(&Beam::new(Coord::new(1, 2), Direction::West))
+        );
+        assert_eq!(engine.count_timelines(), Some(Count::from(2)));
+
+        let engine = engine.with_source(Coord::new(3, 0), Direction::South);
+        assert_eq!(engine.count_timelines(), Some(Count::from(3)));
+    }
+
+    #[test]
+    fn test_loop_has_no_timeline_count() {
+        let grid = Grid::new_from_string(&"/.\\\n...\n\\./".to_string());
+        let engine = BeamEngine::new(&grid).with_source(Coord::new(1, 0), Direction::East);
+        assert_eq!(engine.run().beams.len(), 8);
+        assert_eq!(engine.count_timelines(), None);
+    }
+
+    #[test]
+    fn test_timelines_through_cells() {
+        let grid = Grid::new_from_string(&EXAMPLE.to_string());
+        let timelines = BeamEngine::new(&grid).timelines().unwrap();
+        // Every timeline starts at S and goes through the first splitter.
+        assert_eq!(timelines.through(&Coord::new(7, 0)), Count::from(40));
+        assert_eq!(timelines.through(&Coord::new(7, 2)), Count::from(40));
+        // Nothing reaches the corner.
+        assert_eq!(timelines.through(&Coord::new(0, 0)), Count::from(0));
+        // Timelines through the bottom row add up to all of them.
+        let bottom: Count = (0..=grid.max_x)
+            .map(|x| timelines.through(&Coord::new(x, grid.max_y)))
+            .fold(Count::default(), |acc, x| &acc + &x);
+        assert_eq!(bottom, Count::from(40));
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let grid = Grid::new_from_string(&"..S..\n..^..\n.^...\n.....".to_string());
+        let timelines = BeamEngine::new(&grid).timelines().unwrap();
+        assert_eq!(timelines.total, Count::from(3));
+        assert_eq!(timelines.heatmap(&grid), "..@..\n.@^:.\n:^::.\n:.::.");
+    }
+
+    #[test]
+    fn test_timelines_past_u64() {
+        // 70 rows of splitters is 2^70 timelines.
+        let rows = 70;
+        let width = 2 * rows + 3;
+        let mut string = format!("{}S{}\n", ".".repeat(width / 2), ".".repeat(width / 2));
+        for row in 0..rows {
+            let mut line = vec!['.'; width];
+            for x in (width / 2 - row..=width / 2 + row).step_by(2) {
+                line[x] = '^';
+            }
+            string += &line.into_iter().collect::<String>();
+            string += "\n";
+        }
+        let grid = Grid::new_from_string(&string);
+        let total = BeamEngine::new(&grid).count_timelines().unwrap();
+        assert_eq!(total.to_string(), (1u128 << rows).to_string());
+        assert_eq!(total.to_u64(), None);
+    }
+
+    #[test]
+    fn test_beam_frames() {
+        let grid = Grid::new_from_string(&"..S..\n..^..\n.....".to_string());
+        let engine = BeamEngine::new(&grid);
+        let waves = engine.waves();
+        assert_eq!(waves.len(), 4);
+        assert_eq!(
+            waves[2],
+            HashSet::from([Coord::new(1, 1), Coord::new(3, 1)])
+        );
+
+        let frames = beam_frames(&engine);
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[0].grid, grid);
+        let last = Grid::new_from_string(&"..S..\n.|^|.\n.|.|.".to_string());
+        assert_eq!(frames[4].grid, last);
+        assert_eq!(frames[4].caption, "Step 4: 2 new cells lit");
+    }
 }
@@ -4,6 +4,10 @@ use std::{
     collections::{BTreeMap, HashMap, HashSet},
     fmt::Debug,
 };
+use utilities::{
+    parse::{self, integer, lines, map, separated_pair, tag},
+    trace,
+};
 
 /// Struct representing a junction box with its coordinates.
 #[derive(Clone, Copy, Hash, Debug, Eq, PartialEq)]
@@ -70,17 +74,15 @@ impl Debug for Circuit {
 
 /// Parse the incoming file to Vec of JunctionBoxes.
 fn parse_text(string: &String) -> Vec<JunctionBox> {
-    let mut junction_boxes: Vec<JunctionBox> = Vec::new();
-
-    for line in string.lines() {
-        let v: Vec<&str> = line.split(',').collect();
-        junction_boxes.push(JunctionBox::new(
-            v[0].parse().expect("X Failed to parse."),
-            v[1].parse().expect("Y Failed to parse."),
-            v[2].parse().expect("Z Failed to parse."),
-        ));
-    }
-    junction_boxes
+    let junction_box = map(
+        separated_pair(
+            integer(),
+            tag(","),
+            separated_pair(integer(), tag(","), integer()),
+        ),
+        |(x, (y, z))| JunctionBox::new(x, y, z),
+    );
+    parse::all(lines(junction_box), string).unwrap_or_else(|error| panic!("{error}"))
 }
 
 /// Get a BTreeMap of distances with the corresponding JunctionBoxes.
@@ -164,7 +166,7 @@ fn part1(file_name: &str, number_circuits: usize) -> usize {
         .into_iter()
         .rev()
         .take(3)
-        .inspect(|x| println!("{x}"))
+        .inspect(|x| trace!(Debug, "Circuit size: {x}"))
         .product()
 }
 
@@ -185,6 +187,8 @@ fn part2(file_name: &str) -> i64 {
 
 /// Main function / code entry point.
 fn main() {
+    utilities::trace::init_from_args(std::env::args().skip(1));
+
     println!("Sum for example1: {}", part1("example1.txt", 10));
     println!("Sum for input: {}", part1("input.txt", 1000));
     println!("Sum for example1 part2: {}", part2("example1.txt"));
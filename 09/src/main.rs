@@ -1,131 +1,517 @@
 //! Day 09: Movie Theater
 
-use std::iter;
-use utilities::{Coord, coord};
+use std::fmt;
+
+use utilities::{
+    Coord, coord,
+    geometry::{Orientation, Polygon, Segment, bounds, tile_area},
+    parse::{self, integer, lines, map, separated_pair, tag},
+    trace,
+};
 
 /// Parse the incoming file to Vec of Coords.
 fn parse_text(string: &String) -> Vec<Coord> {
-    let mut coords: Vec<Coord> = Vec::new();
+    let red_tile = map(separated_pair(integer(), tag(","), integer()), |(x, y)| {
+        coord!(x, y)
+    });
+    parse::all(lines(red_tile), string).unwrap_or_else(|error| panic!("{error}"))
+}
 
-    for line in string.lines() {
-        let v: Vec<&str> = line.split(',').collect();
-        coords.push(coord!(
-            v[0].parse().expect("X Failed to parse."),
-            v[1].parse().expect("Y Failed to parse.")
-        ));
+/// Why the red tiles don't make a loop that part 2 can use. Tiles are
+/// referred to by their line in the input, counting from 1.
+#[derive(Debug, PartialEq, Eq)]
+enum LoopError {
+    /// A loop needs at least four corners.
+    TooFewTiles(usize),
+    /// The same tile twice in a row.
+    Repeated {
+        line: usize,
+        next_line: usize,
+        tile: Coord,
+    },
+    /// Two tiles in a row aren't in the same row or column. The last tile's
+    /// next line is the first, which is the loop closing.
+    Diagonal {
+        line: usize,
+        next_line: usize,
+        from: Coord,
+        to: Coord,
+    },
+    /// Two edges of the loop touch or cross, sharing the tiles from `at.0` to
+    /// `at.1`. Each edge goes with the line it starts on.
+    SelfIntersecting {
+        first: (usize, Segment),
+        second: (usize, Segment),
+        at: (Coord, Coord),
+    },
+}
+
+impl fmt::Display for LoopError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TooFewTiles(count) => {
+                write!(f, "only {count} red tiles, a loop needs at least 4")
+            }
+            Self::Repeated {
+                line,
+                next_line,
+                tile,
+            } => write!(f, "lines {line} and {next_line} are both {tile}"),
+            Self::Diagonal {
+                line,
+                next_line,
+                from,
+                to,
+            } => {
+                write!(
+                    f,
+                    "line {line} {from} and line {next_line} {to} aren't in the same row or column"
+                )?;
+                if *next_line == 1 {
+                    write!(f, ", so the loop doesn't close")?;
+                }
+                Ok(())
+            }
+            Self::SelfIntersecting { first, second, at } => {
+                write!(
+                    f,
+                    "the edge from line {} {} to {} meets the edge from line {} {} to {} at {}",
+                    first.0,
+                    first.1.start,
+                    first.1.end,
+                    second.0,
+                    second.1.start,
+                    second.1.end,
+                    at.0
+                )?;
+                if at.0 != at.1 {
+                    write!(f, " to {}", at.1)?;
+                }
+                Ok(())
+            }
+        }
     }
-    coords
+}
+
+/// Check each red tile is in the same row or column as the next, including
+/// the last and first, and returns the loop they make.
+fn check_corners(red_tiles: &[Coord]) -> Result<Polygon, LoopError> {
+    if red_tiles.len() < 4 {
+        return Err(LoopError::TooFewTiles(red_tiles.len()));
+    }
+    let polygon = Polygon::new(red_tiles.to_vec());
+    let edges: Vec<Segment> = polygon.edges().collect();
+    let line = |edge: usize| edge + 1;
+    let next_line = |edge: usize| (edge + 1) % edges.len() + 1;
+    for (i, edge) in edges.iter().enumerate() {
+        if edge.start == edge.end {
+            return Err(LoopError::Repeated {
+                line: line(i),
+                next_line: next_line(i),
+                tile: edge.start,
+            });
+        }
+        if !edge.is_axis_aligned() {
+            return Err(LoopError::Diagonal {
+                line: line(i),
+                next_line: next_line(i),
+                from: edge.start,
+                to: edge.end,
+            });
+        }
+    }
+    Ok(polygon)
+}
+
+/// Check the red tiles make a loop part 2 can use, as `check_corners` does,
+/// and also that the loop never touches or crosses itself. Returns the checked
+/// loop and which way it goes round. The edges are all along rows and columns
+/// by then, so finding where the loop meets itself is a sweep rather than
+/// checking every pair.
+fn validate_loop(red_tiles: &[Coord]) -> Result<(Polygon, Orientation), LoopError> {
+    let polygon = check_corners(red_tiles)?;
+    let edges: Vec<Segment> = polygon.edges().collect();
+    let line = |edge: usize| edge + 1;
+    if let Some((i, j)) = polygon.find_self_intersection() {
+        // Edges along rows and columns meet wherever their boxes overlap.
+        let (first_min, first_max) = bounds([&edges[i].start, &edges[i].end]).unwrap();
+        let (second_min, second_max) = bounds([&edges[j].start, &edges[j].end]).unwrap();
+        let at = (
+            coord!(first_min.x.max(second_min.x), first_min.y.max(second_min.y)),
+            coord!(first_max.x.min(second_max.x), first_max.y.min(second_max.y)),
+        );
+        return Err(LoopError::SelfIntersecting {
+            first: (line(i), edges[i]),
+            second: (line(j), edges[j]),
+            at,
+        });
+    }
+    let orientation = polygon
+        .orientation()
+        .expect("A simple loop of four or more corners has an area.");
+    Ok((polygon, orientation))
+}
+
+/// The `count` largest rectangles by area with red tiles in opposite corners
+/// that pass `is_allowed`, biggest first. Each is the area and the two
+/// corners.
+///
+/// Pairs are pruned by area. Each tile gets an upper bound on the area it can
+/// make, from how far it is from the edges of the bounding box. Tiles are
+/// tried biggest bound first, pairs that can't beat the smallest kept so far
+/// are skipped without calling `is_allowed`, and once a tile's bound can't
+/// beat it, nothing after it can either.
+fn largest_rectangles(
+    red_tiles: &[Coord],
+    count: usize,
+    is_allowed: impl Fn(&Coord, &Coord) -> bool,
+) -> Vec<(u128, Coord, Coord)> {
+    let Some((min, max)) = bounds(red_tiles) else {
+        return Vec::new();
+    };
+    let bound = |tile: &Coord| {
+        let far_x = if tile.x - min.x > max.x - tile.x {
+            min.x
+        } else {
+            max.x
+        };
+        let far_y = if tile.y - min.y > max.y - tile.y {
+            min.y
+        } else {
+            max.y
+        };
+        tile_area(tile, &coord!(far_x, far_y))
+    };
+    let mut tiles: Vec<(u128, Coord)> = red_tiles.iter().map(|t| (bound(t), *t)).collect();
+    tiles.sort_by_key(|&(bound, _)| std::cmp::Reverse(bound));
+
+    let mut best: Vec<(u128, Coord, Coord)> = Vec::with_capacity(count + 1);
+    // Anything this size or smaller can't make it in.
+    let beaten = |best: &[(u128, Coord, Coord)], area: u128| {
+        count == 0 || best.len() == count && area <= best[count - 1].0
+    };
+    let mut checked = 0;
+    for (i, &(bound, a)) in tiles.iter().enumerate() {
+        if beaten(&best, bound) {
+            break;
+        }
+        for &(b_bound, b) in &tiles[i + 1..] {
+            // The area can't beat either tile's bound, and the bounds only
+            // get smaller from here.
+            if beaten(&best, b_bound) {
+                break;
+            }
+            let area = tile_area(&a, &b);
+            if beaten(&best, area) {
+                continue;
+            }
+            checked += 1;
+            if is_allowed(&a, &b) {
+                let at = best.partition_point(|&(kept, _, _)| kept >= area);
+                best.insert(at, (area, a, b));
+                best.truncate(count);
+            }
+        }
+    }
+    trace!(Info, "largest_rectangles checked {checked} candidates.");
+    best
+}
+
+/// Largest rectangle by area with red tiles in opposite corners that passes
+/// `is_allowed`. Returns the area and the two corners.
+fn largest_rectangle(
+    red_tiles: &[Coord],
+    is_allowed: impl Fn(&Coord, &Coord) -> bool,
+) -> Option<(u128, Coord, Coord)> {
+    largest_rectangles(red_tiles, 1, is_allowed).pop()
 }
 
 /// Find the biggest area between Coords
-fn get_biggest_area(coords: &Vec<Coord>) -> u64 {
-    let areas = get_areas(coords);
-    println!("get_biggest_area checked {} combinations.", areas.len());
-
-    // Grab first since areas is already sorted by area.
-    areas
-        .first()
-        .expect("get_biggest_area: area.first() is empty.")
+fn get_biggest_area(coords: &[Coord]) -> u128 {
+    largest_rectangle(coords, |_, _| true)
+        .expect("get_biggest_area: needs at least two tiles.")
         .0
 }
 
-/// Returns all the areas with the two Coord that made it, sorted by area.
-fn get_areas(coords: &Vec<Coord>) -> Vec<(u64, Coord, Coord)> {
-    let area_iter = (0..coords.len()).flat_map(move |a| {
-        (a + 1..coords.len()).map(move |b| {
-            let coord_a = coords[a];
-            let coord_b = coords[b];
-            let area = calculate_area(&coord_a, &coord_b);
-            (area, coord_a, coord_b)
-        })
-    });
-    let mut areas: Vec<_> = area_iter.collect();
-    // Sort goes low->high, so we reverse it right afterwards.
-    areas.sort_by_key(|&(area, _, _)| area);
-    areas.reverse();
+/// The red tile loop squashed down to just the rows and columns where
+/// something happens, so any rectangle can be checked in O(1).
+///
+/// Cell (i, j) is the open area strictly between columns `xs[i]` and
+/// `xs[i + 1]` and rows `ys[j]` and `ys[j + 1]`. Nothing changes inside a
+/// cell, so it's either all inside the loop or all outside.
+///
+/// Tiles can also sit on the columns and rows themselves, so for counting the
+/// grid is doubled: even indexes are the columns or rows, and odd indexes are
+/// the gaps between them. A tile on a column or row is inside if a cell next
+/// to it is. Gaps only one apart have no tiles in them, so they never count.
+/// That's where this differs from `Polygon::contains_rect`, which sees the
+/// thin gap between two edges next to each other as outside.
+///
+/// The doubled grid takes 16 bytes for each pair of distinct x and y, so the
+/// limit is how many different rows and columns the tiles are on rather than
+/// how many tiles there are. Tens of thousands of tiles on a few hundred rows
+/// or columns are fine, but 10,000 distinct x and 10,000 distinct y would
+/// need 1.6 GB.
+struct CompressedLoop {
+    /// Every x a red tile is on, sorted.
+    xs: Vec<i32>,
+    /// Every y a red tile is on, sorted.
+    ys: Vec<i32>,
+    /// Whether each cell is inside the loop, indexed `i * (ys.len() - 1) + j`.
+    inside: Vec<bool>,
+    /// 2D prefix sums over the doubled grid of places with tiles outside the
+    /// loop. `outside[u * (2 * ys.len()) + v]` counts those before doubled
+    /// column u and doubled row v.
+    outside: Vec<u32>,
+}
+
+impl CompressedLoop {
+    fn new(polygon: &Polygon) -> Self {
+        let red_tiles = polygon.vertices();
+        let sorted_unique = |mut values: Vec<i32>| {
+            values.sort_unstable();
+            values.dedup();
+            values
+        };
+        let xs = sorted_unique(red_tiles.iter().map(|tile| tile.x).collect());
+        let ys = sorted_unique(red_tiles.iter().map(|tile| tile.y).collect());
+        let cells_x = xs.len().saturating_sub(1);
+        let cells_y = ys.len().saturating_sub(1);
+        let index = |values: &[i32], value: i32| values.binary_search(&value).unwrap();
 
-    areas
+        // Scanline across each row of cells: a vertical edge flips inside and
+        // outside for every cell to its right. Mark where the flips happen...
+        let mut inside = vec![false; cells_x * cells_y];
+        for Segment { start, end } in polygon.edges() {
+            if start.x != end.x {
+                continue;
+            }
+            let i = index(&xs, start.x);
+            if i == cells_x {
+                continue;
+            }
+            let (low, high) = (start.y.min(end.y), start.y.max(end.y));
+            for j in index(&ys, low)..index(&ys, high) {
+                inside[i * cells_y + j] ^= true;
+            }
+        }
+        // ...then carry them along each row.
+        for i in 1..cells_x {
+            for j in 0..cells_y {
+                inside[i * cells_y + j] ^= inside[(i - 1) * cells_y + j];
+            }
+        }
+
+        let mut compressed = Self {
+            xs,
+            ys,
+            inside,
+            outside: Vec::new(),
+        };
+        let (width, height) = (2 * compressed.xs.len(), 2 * compressed.ys.len());
+        let mut outside = vec![0; width * height];
+        for u in 0..width.saturating_sub(1) {
+            for v in 0..height.saturating_sub(1) {
+                outside[(u + 1) * height + v + 1] = u32::from(compressed.is_outside(u, v))
+                    + outside[u * height + v + 1]
+                    + outside[(u + 1) * height + v]
+                    - outside[u * height + v];
+            }
+        }
+        compressed.outside = outside;
+        compressed
+    }
+
+    /// Is the cell inside the loop. Cells off the edge are outside.
+    fn is_inside(&self, i: usize, j: usize) -> bool {
+        let cells_y = self.ys.len() - 1;
+        i < self.xs.len() - 1 && j < cells_y && self.inside[i * cells_y + j]
+    }
+
+    /// Does the place at doubled column u and doubled row v have tiles that
+    /// are outside the loop.
+    fn is_outside(&self, u: usize, v: usize) -> bool {
+        // Cells either side along each axis, or just the one for a gap.
+        let near = |doubled: usize| {
+            if doubled.is_multiple_of(2) {
+                (doubled / 2).saturating_sub(1)..=doubled / 2
+            } else {
+                doubled / 2..=doubled / 2
+            }
+        };
+        let has_tiles = |values: &[i32], doubled: usize| {
+            doubled.is_multiple_of(2) || values[doubled / 2 + 1] - values[doubled / 2] > 1
+        };
+        has_tiles(&self.xs, u)
+            && has_tiles(&self.ys, v)
+            && !near(u).any(|i| near(v).any(|j| self.is_inside(i, j)))
+    }
+
+    /// Is the rectangle between two red tiles all red or green tiles.
+    fn contains_rect(&self, a: &Coord, b: &Coord) -> bool {
+        let (min, max) = bounds([a, b]).unwrap();
+        let index = |values: &[i32], value: i32| {
+            values
+                .binary_search(&value)
+                .expect("contains_rect needs red tiles for corners")
+        };
+        let (u0, u1) = (2 * index(&self.xs, min.x), 2 * index(&self.xs, max.x) + 1);
+        let (v0, v1) = (2 * index(&self.ys, min.y), 2 * index(&self.ys, max.y) + 1);
+        let height = 2 * self.ys.len();
+        let at = |u: usize, v: usize| self.outside[u * height + v];
+        at(u1, v1) + at(u0, v0) - at(u0, v1) - at(u1, v0) == 0
+    }
 }
 
 /// Find the biggest area between the red tiles that form a rectangle within the
-/// polygon.
-fn get_biggest_area_filtered(red_tiles: &Vec<Coord>) -> u64 {
-    let areas = get_areas(red_tiles);
-
-    // Iterate through the areas with a find. Find short circuits to return first
-    // thing that returns true. Since areas is already sorted by max, first item
-    // find returns will be the largest area. We try to "find" an area that
-    // doesn't intersect so we can avoid checking the rest of the areas. (Like I
-    // tried the first time...)
-    areas
+/// polygon. The polygon is the one `validate_loop` checked.
+fn get_biggest_area_filtered(polygon: &Polygon) -> u128 {
+    let compressed = CompressedLoop::new(polygon);
+    largest_rectangle(polygon.vertices(), |a, b| compressed.contains_rect(a, b))
+        .expect("get_biggest_area_filtered: no rectangle fits in the loop.")
+        .0
+}
+
+/// A rectangle between two red tiles to draw on the SVG.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Candidate {
+    area: u128,
+    corners: (Coord, Coord),
+    /// Whether it's all red or green tiles.
+    fits: bool,
+}
+
+/// Space around the picture in the SVG, in pixels.
+const SVG_MARGIN: f64 = 10.0;
+
+/// Draw the red tile loop as an SVG scaled to `size` pixels along its longest
+/// side. The inside of the loop is green with the red tiles as dots on its
+/// corners. Candidates are dashed outlines, orange if they fit and grey if
+/// not, with their area shown on hover. The best rectangle is shaded blue.
+fn render_svg(
+    red_tiles: &[Coord],
+    candidates: &[Candidate],
+    best: Option<&Candidate>,
+    size: u32,
+) -> String {
+    let Some((min, max)) = bounds(red_tiles) else {
+        return String::new();
+    };
+    let tiles_across = f64::from((max.x - min.x + 1).max(max.y - min.y + 1));
+    let scale = f64::from(size) / tiles_across;
+    let x = |x: i32| f64::from(x - min.x) * scale + SVG_MARGIN;
+    let y = |y: i32| f64::from(y - min.y) * scale + SVG_MARGIN;
+    // Lines stay visible however far it's zoomed out.
+    let stroke = scale.clamp(1.0, 4.0);
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\">\n",
+        x(max.x + 1) + SVG_MARGIN,
+        y(max.y + 1) + SVG_MARGIN
+    );
+    svg += "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n";
+    let points: Vec<String> = red_tiles
         .iter()
-        .find(|(_area, a, b)| {
-            // Get the min and max bounds for the two coordinates for this area.
-            let bounds = bounds(a, b);
-            // First part of this iterator is just the insanity needed to iterate
-            // like a "window(2)" would with a slice. The iter:once's are there
-            // so we can see the (last,first) combination of red_tiles.
-            !red_tiles
-                .iter()
-                .chain(iter::once(&red_tiles[0]))
-                .zip(red_tiles.iter().chain(iter::once(&red_tiles[0])).skip(1))
-                // Any short circuits if it ever gets true. Saves iterations for
-                // lines that intersect the bounds of the area. Each "line" is two
-                // Coords from the red_tiles.
-                .any(|line| intersecting_line(line, bounds))
+        .map(|tile| {
+            format!(
+                "{:.2},{:.2}",
+                x(tile.x) + scale / 2.0,
+                y(tile.y) + scale / 2.0
+            )
         })
-        .expect("Iterator in get_biggest_area_filtered failed to get a value.")
-        .0
+        .collect();
+    // The points are tile centres, so a tile wide outline covers the edge
+    // tiles too.
+    svg += &format!(
+        "<polygon points=\"{}\" fill=\"#3a3\" stroke=\"#3a3\" stroke-width=\"{:.2}\"/>\n",
+        points.join(" "),
+        scale.max(1.0)
+    );
+
+    let rect = |candidate: &Candidate, style: &str| {
+        let (low, high) = bounds([&candidate.corners.0, &candidate.corners.1]).unwrap();
+        format!(
+            "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" {style}>\
+             <title>{} from {} to {}{}</title></rect>\n",
+            x(low.x),
+            y(low.y),
+            x(high.x + 1) - x(low.x),
+            y(high.y + 1) - y(low.y),
+            candidate.area,
+            candidate.corners.0,
+            candidate.corners.1,
+            if candidate.fits { "" } else { ", doesn't fit" }
+        )
+    };
+    for candidate in candidates {
+        let color = if candidate.fits { "orange" } else { "grey" };
+        svg += &rect(
+            candidate,
+            &format!(
+                "fill=\"none\" stroke=\"{color}\" stroke-width=\"{stroke:.2}\" \
+                 stroke-dasharray=\"{:.2}\"",
+                3.0 * stroke
+            ),
+        );
+    }
+    if let Some(best) = best {
+        svg += &rect(
+            best,
+            &format!(
+                "fill=\"blue\" fill-opacity=\"0.3\" stroke=\"blue\" stroke-width=\"{stroke:.2}\""
+            ),
+        );
+    }
+
+    let radius = (scale / 2.0).max(1.5);
+    for tile in red_tiles {
+        svg += &format!(
+            "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{radius:.2}\" fill=\"red\"/>\n",
+            x(tile.x) + scale / 2.0,
+            y(tile.y) + scale / 2.0
+        );
+    }
+    svg += "</svg>\n";
+    svg
 }
 
-/// Gets the bounds for the two coordinates as a tuple (x,y) where x=min and
-/// y=max
-fn bounds(a: &Coord, b: &Coord) -> (Coord, Coord) {
-    let min = Coord::new(a.x.min(b.x), a.y.min(b.y));
-    let max = Coord::new(a.x.max(b.x), a.y.max(b.y));
-    (min, max)
-}
-
-/// Checks if the line, represented by two Coords (line_coord1,line_coord2),
-/// intersects with the bounds (min,max) of the area. Returns true if it intersects.
-/// Big thanks to https://github.com/connorslade/advent-of-code/blob/main/aoc_2025/src/day_09.rs
-/// because I definitely struggled figuring this out...
-fn intersecting_line(
-    (line_coord1, line_coord2): (&Coord, &Coord),
-    (min, max): (Coord, Coord),
-) -> bool {
-    let (line_min, line_max) = bounds(line_coord1, line_coord2);
-
-    // Horizontal Line
-    line_coord1.x == line_coord2.x 
-        // Line's Y bounds within area's Y bounds.
-        && (((line_min.y < min.y && line_max.y > min.y) 
-            || (line_min.y < max.y && line_max.y > max.y)
-            || (line_min.y >= min.y && line_max.y <= max.y))
-            // Line's X coordinate within the area's X bounds.
-            && line_coord1.x > min.x
-            && line_coord1.x < max.x)
-        // Vertical Line
-        || line_coord1.y == line_coord2.y 
-            // Line's X bounds within area's X bounds.
-            && (((line_min.x < min.x && line_max.x > min.x)
-                || (line_min.x < max.x && line_max.x > max.x)
-                || (line_min.x >= min.x && line_max.x <= max.x))
-                // Line's Y coordinate within the area's Y bounds.
-                && line_coord1.y > min.y
-                && line_coord1.y < max.y)
-}
-
-/// Calculate the area of the rectangle between these two points.
-fn calculate_area(coord1: &Coord, coord2: &Coord) -> u64 {
-    // +1s necessary since area includes the coords locations.
-    let x_axis = u64::from(coord1.x.abs_diff(coord2.x) + 1);
-    let y_axis = u64::from(coord1.y.abs_diff(coord2.y) + 1);
-    x_axis * y_axis
+/// Save an SVG of the file's loop with the best rectangle that fits, and the
+/// `top` biggest rectangles whether they fit or not.
+fn save_svg(file_name: &str, path: &str, top: usize, size: u32) {
+    let file_contents = std::fs::read_to_string(file_name).expect("Couldn't open file");
+    let tiles = parse_text(&file_contents);
+    let (polygon, _) = validate_loop(&tiles).unwrap_or_else(|error| panic!("{file_name}: {error}"));
+    let compressed = CompressedLoop::new(&polygon);
+    let fits = |a: &Coord, b: &Coord| compressed.contains_rect(a, b);
+    let candidate = |(area, a, b): (u128, Coord, Coord)| Candidate {
+        area,
+        corners: (a, b),
+        fits: fits(&a, &b),
+    };
+
+    let best = largest_rectangle(&tiles, fits).map(candidate);
+    let candidates: Vec<Candidate> = largest_rectangles(&tiles, top, |_, _| true)
+        .into_iter()
+        .map(candidate)
+        .collect();
+    std::fs::write(path, render_svg(&tiles, &candidates, best.as_ref(), size))
+        .expect("Couldn't write the SVG");
+    println!("Saved {path}");
+    if let Some(best) = best {
+        let (a, b) = best.corners;
+        println!("Best rectangle: {} from {a} to {b}", best.area);
+    }
+    let rejected = candidates.iter().filter(|c| !c.fits).count();
+    if !candidates.is_empty() {
+        println!(
+            "{rejected} of the {} biggest rectangles don't fit",
+            candidates.len()
+        );
+    }
 }
 
 /// Find the largest rectangle by area from pairs of points.
-fn part1(file_name: &str) -> u64 {
+fn part1(file_name: &str) -> u128 {
     let file_contents = std::fs::read_to_string(file_name).expect("Couldn't open file");
     let tiles = parse_text(&file_contents);
     get_biggest_area(&tiles)
@@ -133,14 +519,67 @@ fn part1(file_name: &str) -> u64 {
 
 /// Find the largest rectangle by area from pairs of points. All of rectangle must
 /// be within the polygon made from the total collection of points.
-fn part2(file_name: &str) -> u64 {
+fn part2(file_name: &str) -> u128 {
+    let file_contents = std::fs::read_to_string(file_name).expect("Couldn't open file");
+    let tiles = parse_text(&file_contents);
+    let (polygon, orientation) =
+        validate_loop(&tiles).unwrap_or_else(|error| panic!("{file_name}: {error}"));
+    trace!(Info, "{file_name}: the loop goes {orientation}.");
+    get_biggest_area_filtered(&polygon)
+}
+
+/// Check the file's red tiles make a loop and print what's wrong, or which
+/// way the loop goes round.
+fn check(file_name: &str) {
     let file_contents = std::fs::read_to_string(file_name).expect("Couldn't open file");
     let tiles = parse_text(&file_contents);
-    get_biggest_area_filtered(&tiles)
+    match validate_loop(&tiles) {
+        Ok((_, orientation)) => println!(
+            "{file_name}: {} red tiles in a loop going {orientation}",
+            tiles.len()
+        ),
+        Err(error) => println!("{file_name}: {error}"),
+    }
 }
 
 /// Main function / code entry point.
 fn main() {
+    let args = utilities::trace::init_from_args(std::env::args().skip(1));
+    if let Some((file_name, flags)) = args.split_first() {
+        let usage = "Usage: day09 <file> [--check] [--svg out.svg] [--top n] [--size px]";
+        let mut checking = false;
+        let mut svg = None;
+        let mut top = 0;
+        let mut size = 800;
+        let mut flags = flags.iter();
+        while let Some(flag) = flags.next() {
+            match flag.as_str() {
+                "--check" => checking = true,
+                "--svg" => svg = Some(flags.next().expect(usage)),
+                "--top" => {
+                    let n = flags.next().expect(usage);
+                    top = n.parse().unwrap_or_else(|_| panic!("Bad count: {n}"));
+                }
+                "--size" => {
+                    let px = flags.next().expect(usage);
+                    size = px.parse().unwrap_or_else(|_| panic!("Bad size: {px}"));
+                }
+                _ => panic!("{usage}"),
+            }
+        }
+        if checking {
+            check(file_name);
+        }
+        if let Some(path) = svg {
+            save_svg(file_name, path, top, size);
+        }
+        if !checking && svg.is_none() {
+            println!("Sum for {file_name}: {}", part1(file_name));
+            println!("Sum for {file_name} part2: {}", part2(file_name));
+        }
+        return;
+    }
+
     println!("Sum for example1: {}", part1("example1.txt"));
     println!("Sum for input: {}", part1("input.txt"));
     println!("Sum for example1 part2: {}", part2("example1.txt"));
@@ -178,21 +617,294 @@ mod tests {
     }
 
     #[test]
-    fn test_calculate_area() {
+    fn test_tile_area() {
         let c1 = coord!(2, 5);
         let c2 = coord!(9, 7);
-        assert_eq!(calculate_area(&c1, &c2), 24);
+        assert_eq!(tile_area(&c1, &c2), 24);
 
         let c1 = coord!(7, 1);
         let c2 = coord!(11, 7);
-        assert_eq!(calculate_area(&c1, &c2), 35);
+        assert_eq!(tile_area(&c1, &c2), 35);
 
         let c1 = coord!(7, 3);
         let c2 = coord!(2, 3);
-        assert_eq!(calculate_area(&c1, &c2), 6);
+        assert_eq!(tile_area(&c1, &c2), 6);
 
         let c1 = coord!(2, 5);
         let c2 = coord!(11, 1);
-        assert_eq!(calculate_area(&c1, &c2), 50);
+        assert_eq!(tile_area(&c1, &c2), 50);
+    }
+
+    /// The puzzle's example.
+    const EXAMPLE: &str = "7,1\n11,1\n11,7\n9,7\n9,5\n2,5\n2,3\n7,3\n";
+
+    /// Every tile inside or on the loop, found by filling from outside it.
+    /// Only for small loops.
+    fn filled_tiles(red_tiles: &[Coord]) -> std::collections::HashSet<Coord> {
+        use std::collections::HashSet;
+        let mut border = HashSet::new();
+        for edge in Polygon::new(red_tiles.to_vec()).edges() {
+            let (min, max) = bounds([&edge.start, &edge.end]).unwrap();
+            for x in min.x..=max.x {
+                for y in min.y..=max.y {
+                    border.insert(coord!(x, y));
+                }
+            }
+        }
+        let (lo, hi) = (coord!(-1, -1), coord!(20, 20));
+        let mut outside = HashSet::from([lo]);
+        let mut stack = vec![lo];
+        while let Some(tile) = stack.pop() {
+            for next in [tile.north(), tile.east(), tile.south(), tile.west()] {
+                if next.x >= lo.x
+                    && next.y >= lo.y
+                    && next.x <= hi.x
+                    && next.y <= hi.y
+                    && !border.contains(&next)
+                    && outside.insert(next)
+                {
+                    stack.push(next);
+                }
+            }
+        }
+        (lo.x..=hi.x)
+            .flat_map(|x| (lo.y..=hi.y).map(move |y| coord!(x, y)))
+            .filter(|tile| !outside.contains(tile))
+            .collect()
+    }
+
+    /// Check every pair against the filled in tiles.
+    fn biggest_area_brute_force(red_tiles: &[Coord]) -> u128 {
+        let filled = filled_tiles(red_tiles);
+        let mut best = 0;
+        for a in red_tiles {
+            for b in red_tiles {
+                let (min, max) = bounds([a, b]).unwrap();
+                let fits = (min.x..=max.x)
+                    .all(|x| (min.y..=max.y).all(|y| filled.contains(&coord!(x, y))));
+                if fits {
+                    best = best.max(tile_area(a, b));
+                }
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn test_example() {
+        let tiles = parse_text(&EXAMPLE.to_string());
+        assert_eq!(get_biggest_area(&tiles), 50);
+        let (polygon, _) = validate_loop(&tiles).unwrap();
+        assert_eq!(get_biggest_area_filtered(&polygon), 24);
+        assert_eq!(biggest_area_brute_force(&tiles), 24);
+    }
+
+    #[test]
+    fn test_compressed_loop_against_brute_force() {
+        // Each loop and whether it has edges right next to each other.
+        let loops = [
+            // U shape, so the big rectangle across the top of the U is out.
+            ("1,1\n5,1\n5,9\n9,9\n9,1\n13,1\n13,12\n1,12\n", false),
+            // Anticlockwise comb with teeth one tile apart.
+            (
+                "0,0\n0,10\n2,10\n2,3\n3,3\n3,10\n5,10\n5,3\n6,3\n6,10\n8,10\n8,0\n",
+                true,
+            ),
+            // Staircase.
+            ("0,0\n4,0\n4,2\n7,2\n7,5\n10,5\n10,9\n0,9\n", false),
+            // Plus sign.
+            (
+                "4,0\n7,0\n7,4\n11,4\n11,7\n7,7\n7,11\n4,11\n4,7\n0,7\n0,4\n4,4\n",
+                false,
+            ),
+        ];
+        for (string, adjacent_edges) in loops {
+            let tiles = parse_text(&string.to_string());
+            let (polygon, _) = validate_loop(&tiles).unwrap();
+            let compressed = CompressedLoop::new(&polygon);
+            let filled = filled_tiles(&tiles);
+            for a in &tiles {
+                for b in &tiles {
+                    let (min, max) = bounds([a, b]).unwrap();
+                    let fits = (min.x..=max.x)
+                        .all(|x| (min.y..=max.y).all(|y| filled.contains(&coord!(x, y))));
+                    assert_eq!(
+                        compressed.contains_rect(a, b),
+                        fits,
+                        "{a} {b} in {string:?}"
+                    );
+                    // Without a thin gap, tiles and area agree.
+                    if !adjacent_edges {
+                        assert_eq!(polygon.contains_rect(a, b), fits, "{a} {b} in {string:?}");
+                    }
+                }
+            }
+            assert_eq!(
+                get_biggest_area_filtered(&polygon),
+                biggest_area_brute_force(&tiles)
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_loop() {
+        let validate = |string: &str| {
+            validate_loop(&parse_text(&string.to_string())).map(|(_, orientation)| orientation)
+        };
+        assert_eq!(validate(EXAMPLE), Ok(Orientation::Clockwise));
+        assert_eq!(
+            validate("7,3\n2,3\n2,5\n9,5\n9,7\n11,7\n11,1\n7,1\n"),
+            Ok(Orientation::Anticlockwise)
+        );
+        assert_eq!(validate("1,1\n5,1\n5,5\n"), Err(LoopError::TooFewTiles(3)));
+        assert_eq!(
+            validate("1,1\n5,1\n5,1\n5,5\n1,5\n"),
+            Err(LoopError::Repeated {
+                line: 2,
+                next_line: 3,
+                tile: coord!(5, 1)
+            })
+        );
+
+        let diagonal = validate("1,1\n5,1\n6,5\n1,5\n").unwrap_err();
+        assert_eq!(
+            diagonal.to_string(),
+            "line 2 (5, 1) and line 3 (6, 5) aren't in the same row or column"
+        );
+        let open = validate("1,1\n5,1\n5,5\n2,5\n").unwrap_err();
+        assert!(open.to_string().ends_with("so the loop doesn't close"));
+
+        // The edge along the bottom cuts across the U, which only the full
+        // check finds.
+        let tiles = parse_text(&"1,1\n3,1\n3,6\n5,6\n5,1\n7,1\n7,4\n1,4\n".to_string());
+        assert!(check_corners(&tiles).is_ok());
+        let crossing = validate_loop(&tiles).unwrap_err();
+        assert_eq!(
+            crossing.to_string(),
+            "the edge from line 2 (3, 1) to (3, 6) meets the edge from line 7 (7, 4) to (1, 4) at (3, 4)"
+        );
+        // Doubling back along an edge.
+        let back = validate("1,1\n6,1\n3,1\n3,5\n1,5\n").unwrap_err();
+        assert_eq!(
+            back,
+            LoopError::SelfIntersecting {
+                first: (1, Segment::new(coord!(1, 1), coord!(6, 1))),
+                second: (2, Segment::new(coord!(6, 1), coord!(3, 1))),
+                at: (coord!(3, 1), coord!(6, 1)),
+            }
+        );
+        assert!(back.to_string().ends_with("at (3, 1) to (6, 1)"));
+    }
+
+    #[test]
+    fn test_largest_rectangles() {
+        let tiles = parse_text(&EXAMPLE.to_string());
+        let top = largest_rectangles(&tiles, 3, |_, _| true);
+        let areas: Vec<u128> = top.iter().map(|&(area, _, _)| area).collect();
+        let mut all: Vec<u128> = (0..tiles.len())
+            .flat_map(|i| (i + 1..tiles.len()).map(move |j| (i, j)))
+            .map(|(i, j)| tile_area(&tiles[i], &tiles[j]))
+            .collect();
+        all.sort_unstable_by(|a, b| b.cmp(a));
+        assert_eq!(areas, all[..3]);
+        assert_eq!(areas, [50, 50, 40]);
+        assert_eq!(largest_rectangles(&tiles, 0, |_, _| true), []);
+        assert_eq!(largest_rectangles(&tiles, 100, |_, _| true).len(), 28);
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let tiles = parse_text(&EXAMPLE.to_string());
+        let best = Candidate {
+            area: 24,
+            corners: (coord!(9, 5), coord!(2, 3)),
+            fits: true,
+        };
+        let rejected = Candidate {
+            area: 50,
+            corners: (coord!(2, 5), coord!(11, 1)),
+            fits: false,
+        };
+        // 10 tiles across at 10px each, plus the margins.
+        let svg = render_svg(&tiles, &[rejected], Some(&best), 100);
+        assert!(
+            svg.starts_with(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"120\" height=\"90\">"
+            )
+        );
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<circle").count(), tiles.len());
+        assert!(svg.contains("<polygon points=\"65.00,15.00 105.00,15.00 "));
+        assert!(svg.contains(
+            "<rect x=\"10.00\" y=\"30.00\" width=\"80.00\" height=\"30.00\" fill=\"blue\""
+        ));
+        assert!(svg.contains("<title>24 from (9, 5) to (2, 3)</title>"));
+        assert!(svg.contains("stroke=\"grey\""));
+        assert!(svg.contains("<title>50 from (2, 5) to (11, 1), doesn't fit</title>"));
+    }
+
+    #[test]
+    fn test_many_tiles() {
+        // Straight down the left, then zig-zag back up the right between x=100
+        // and x=150, for over 10,000 red tiles.
+        let height = 10_000;
+        let mut tiles = vec![coord!(0, 0), coord!(0, height), coord!(100, height)];
+        let (mut x, mut y) = (100, height);
+        while y > 0 {
+            y -= 2;
+            tiles.push(coord!(x, y));
+            x = if x == 100 { 150 } else { 100 };
+            tiles.push(coord!(x, y));
+        }
+        assert!(tiles.len() > 10_000);
+        let (polygon, orientation) = validate_loop(&tiles).unwrap();
+        assert_eq!(orientation, Orientation::Anticlockwise);
+        // Pulling one zig out past x=0 makes it cross the left hand edge.
+        let mut crossed = tiles.clone();
+        crossed[6].x = -10;
+        crossed[7].x = -10;
+        assert!(matches!(
+            validate_loop(&crossed),
+            Err(LoopError::SelfIntersecting { .. })
+        ));
+        let area = get_biggest_area_filtered(&polygon);
+        assert_eq!(area, 101 * (u128::try_from(height).unwrap() + 1));
+    }
+
+    /// Stairs going right and down `steps` times, `size` apart, then straight
+    /// back along the bottom and up the left. Every step is on its own row
+    /// and column.
+    fn staircase(steps: i32, size: i32) -> Vec<Coord> {
+        let mut tiles: Vec<Coord> = (0..steps)
+            .flat_map(|k| [coord!(k * size, k * size), coord!((k + 1) * size, k * size)])
+            .collect();
+        tiles.extend([coord!(steps * size, steps * size), coord!(0, steps * size)]);
+        tiles
+    }
+
+    /// The best rectangle goes from the corner of a step to the bottom left.
+    fn staircase_area(steps: i32, size: i32) -> u128 {
+        (0..steps)
+            .map(|k| tile_area(&coord!((k + 1) * size, k * size), &coord!(0, steps * size)))
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_staircase() {
+        let tiles = staircase(5, 3);
+        let (polygon, _) = validate_loop(&tiles).unwrap();
+        assert_eq!(
+            get_biggest_area_filtered(&polygon),
+            biggest_area_brute_force(&tiles)
+        );
+        assert_eq!(biggest_area_brute_force(&tiles), staircase_area(5, 3));
+
+        // Both the rows and the columns grow with the number of tiles here,
+        // unlike test_many_tiles.
+        let tiles = staircase(1500, 3);
+        assert_eq!(tiles.len(), 3002);
+        let (polygon, _) = validate_loop(&tiles).unwrap();
+        assert_eq!(get_biggest_area_filtered(&polygon), staircase_area(1500, 3));
     }
 }
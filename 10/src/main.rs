@@ -5,6 +5,10 @@ use std::{
     fmt::Display,
 };
 
+use utilities::parse::{
+    self, bracketed, integers, lines, list, map, pair, separated_pair, tag, take_while1, terminated,
+};
+
 /// Struct representing a machine.
 #[derive(Clone, Hash, Debug, Eq, PartialEq)]
 struct Machine {
@@ -59,36 +63,25 @@ fn convert_lights(lights: &str) -> u16 {
     u16::from_str_radix(&lights_rev, 2).expect("convert_lights failed.")
 }
 
-/// Convert the joltages string into an array of u16;16. Align the first digit with
+/// Convert the joltages into an array of u16;16. Align the first number with
 /// the last index of the array so it aligns with the bits of the lights.
-fn convert_joltages(joltages: &str) -> [u16; 16] {
+fn convert_joltages(joltages: &[u16]) -> [u16; 16] {
     let mut ret: [u16; 16] = [0; 16];
-    let splits: Vec<u16> = joltages
-        .split(',')
-        .map(|x| {
-            x.parse::<u16>()
-                .expect(&format!("convert_joltages failed for {x}"))
-        })
-        .collect();
-    if splits.len() > 16 {
+    if joltages.len() > 16 {
         panic!("too many joltages.");
     }
-    for (idx, num) in splits.into_iter().enumerate() {
-        ret[ret.len() - 1 - idx] = num;
+    for (idx, num) in joltages.iter().enumerate() {
+        ret[ret.len() - 1 - idx] = *num;
     }
     ret
 }
 
-/// Convert the button schematic string into a u16. Align in reverse index order
-/// so it aligns with the bits of the lights.
-fn convert_button(button: &str) -> u16 {
+/// Convert the lights a button toggles into a u16. Align in reverse index
+/// order so it aligns with the bits of the lights.
+fn convert_button(button: &[usize]) -> u16 {
     let mut bit_array: [bool; 16] = [false; 16];
     button
-        .split(',')
-        .map(|x| {
-            x.parse::<usize>()
-                .expect(&format!("convert_button failed for {x}"))
-        })
+        .iter()
         .for_each(|x| bit_array[bit_array.len() - 1 - x] = true);
     let mut value: u16 = 0;
     for (i, b) in bit_array.iter().rev().enumerate() {
@@ -99,32 +92,29 @@ fn convert_button(button: &str) -> u16 {
     value
 }
 
-/// Parse the incoming file to Vec of Coords.
+/// Parse the incoming file to Vec of Machines. Each line is the lights in
+/// `[...]`, then buttons in `(...)` and the joltages in `{...}`.
 fn parse_text(string: &String) -> Vec<Machine> {
-    let mut machines = Vec::new();
-    let x: &[_] = &['(', ')', '{', '}', '[', ']'];
-
-    for line in string.lines() {
-        let mut string_parts: VecDeque<&str> = line.split(' ').collect();
-        let lights = string_parts
-            .pop_front()
-            .expect("parse_text->lights.")
-            .trim_matches(x);
-        let lights_converted = convert_lights(lights);
-        let joltages = string_parts
-            .pop_back()
-            .expect("parse_text->joltages.")
-            .trim_matches(x);
-        let joltages_converted = convert_joltages(joltages);
-        // At this point, string_parts should only be the buttons.
-        let mut buttons = Vec::new();
-        for part in string_parts {
-            let button = part.trim_matches(x);
-            buttons.push(convert_button(button));
-        }
-        machines.push(Machine::new(lights_converted, buttons, joltages_converted));
-    }
-    machines
+    let lights = bracketed('[', take_while1(|c| c == '.' || c == '#', "lights"));
+    let button = bracketed('(', integers(","));
+    let joltages = bracketed('{', integers(","));
+    let machine = map(
+        pair(
+            terminated(lights, tag(" ")),
+            separated_pair(list(button, " "), tag(" "), joltages),
+        ),
+        |(lights, (buttons, joltages))| {
+            Machine::new(
+                convert_lights(lights),
+                buttons
+                    .iter()
+                    .map(|button| convert_button(button))
+                    .collect(),
+                convert_joltages(&joltages),
+            )
+        },
+    );
+    parse::all(lines(machine), string).unwrap_or_else(|error| panic!("{error}"))
 }
 
 /// For each machine, figure out minimum number of button presses to make indicator
@@ -252,20 +242,39 @@ mod tests {
     #[test]
     fn test_convert_joltages() {
         assert_eq!(
-            convert_joltages("3,5,4,7"),
+            convert_joltages(&[3, 5, 4, 7]),
             [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 7, 4, 5, 3]
         );
         assert_eq!(
-            convert_joltages("10,11,11,5,10,5"),
+            convert_joltages(&[10, 11, 11, 5, 10, 5]),
             [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 5, 10, 5, 11, 11, 10]
         );
     }
 
     #[test]
     fn test_convert_button() {
-        assert_eq!(convert_button("0,1"), 0b11u16);
-        assert_eq!(convert_button("0,2"), 0b101u16);
-        assert_eq!(convert_button("0,1,2"), 0b111u16);
+        assert_eq!(convert_button(&[0, 1]), 0b11u16);
+        assert_eq!(convert_button(&[0, 2]), 0b101u16);
+        assert_eq!(convert_button(&[0, 1, 2]), 0b111u16);
+    }
+
+    #[test]
+    fn test_parse_text() {
+        let machines =
+            parse_text(&"[.##.] (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}\n".to_string());
+        assert_eq!(machines.len(), 1);
+        assert_eq!(machines[0].lights_required, 0b0110);
+        assert_eq!(
+            machines[0].buttons,
+            [0b1000, 0b1010, 0b100, 0b1100, 0b101, 0b11]
+        );
+        assert_eq!(machines[0].joltages, convert_joltages(&[3, 5, 4, 7]));
+    }
+
+    #[test]
+    #[should_panic(expected = "line 2, column 6: expected '('")]
+    fn test_parse_text_fail() {
+        parse_text(&"[.#] (0) {1,2}\n[#.] x {1}\n".to_string());
     }
 
     #[test]
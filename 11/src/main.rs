@@ -5,6 +5,8 @@ use std::{
     fmt::{Debug, Display},
 };
 
+use utilities::parse::{self, key_value, lines, list, take_while1, try_map};
+
 /// Struct representing a device.
 #[derive(Clone, Copy, Hash, Eq, PartialEq)]
 struct Device([char; 3]);
@@ -52,27 +54,16 @@ const FFT: Device = Device(['f', 'f', 't']);
 
 /// Parse the incoming file to HashMap of Device keys with Devices values.
 fn parse_text(string: &String) -> HashMap<Device, Vec<Device>> {
-    // Make an empty HashMap with capacity equal to the number of lines/devices
-    // plus one for the special "out" key.
-    let mut devices = HashMap::with_capacity(string.lines().count() + 1);
-
-    for line in string.lines() {
-        // Split on the colon space to get two pieces of text.
-        let mut string_parts: VecDeque<&str> = line.split(": ").collect();
-        // First piece should be the key/node device. Convert to Device type.
-        let key_device =
-            Device::try_from(string_parts.pop_front().expect("parse_text->key_device.")).unwrap();
-
-        // At this point, string_parts should only be the Devices that are connected
-        // to the key_device. Pop off the VecDeque and split on the spaces to get
-        // what should be a list of the Devices. Convert to Devices and push to
-        // the Vec that will become the value for the entry.
-        let mut connected_devices = Vec::new();
-        for part in string_parts.pop_back().unwrap().split(' ') {
-            connected_devices.push(Device::try_from(part).unwrap());
-        }
-        devices.insert(key_device, connected_devices);
-    }
+    // Each line is a device, a colon, then the devices it connects to.
+    let device = try_map(
+        take_while1(char::is_alphanumeric, "a device name"),
+        |name| Device::try_from(name).map_err(|_| "a 3 letter device name"),
+    );
+    let line = key_value(&device, list(&device, " "));
+    let mut devices: HashMap<Device, Vec<Device>> = parse::all(lines(line), string)
+        .unwrap_or_else(|error| panic!("{error}"))
+        .into_iter()
+        .collect();
 
     // Insert the "out" Device.
     devices.insert(OUT, Vec::new());
@@ -225,6 +216,28 @@ mod tests {
         assert_eq!(part2, 306594217920240);
     }
 
+    #[test]
+    fn test_parse_text() {
+        let devices = parse_text(&"you: bbb ccc\nbbb: out\n".to_string());
+        assert_eq!(devices.len(), 3);
+        assert_eq!(
+            devices[&YOU],
+            [
+                Device::try_from("bbb").unwrap(),
+                Device::try_from("ccc").unwrap()
+            ]
+        );
+        assert_eq!(devices[&OUT], []);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "line 2, column 10: expected a 3 letter device name, found \"ccccc\""
+    )]
+    fn test_parse_text_bad_device() {
+        parse_text(&"you: bbb\nbbb: out ccccc\n".to_string());
+    }
+
     #[test]
     fn test_convert_to_device() {
         assert_eq!(
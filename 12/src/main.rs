@@ -1,7 +1,14 @@
 //! Day 12: Christmas Tree Farm
 
 use std::collections::HashMap;
-use utilities::{Coord, coord};
+use utilities::{
+    Coord, coord,
+    parse::{
+        self, Parsed, blank_line, integer, integers, key_value, line_end, lines, map, pair,
+        sections, separated_pair, tag, take_while1, terminated, try_map,
+    },
+    trace,
+};
 
 #[derive(Clone, Copy, Debug, Default, Hash, Eq, PartialEq)]
 struct Region {
@@ -56,47 +63,20 @@ impl Region {
     }
 }
 
-impl TryFrom<&str> for Region {
-    type Error = &'static str;
-
-    /// Tries to convert &str to a Region. Panics if &str has wrong data.
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let (area, shapes) = match value.split_once(": ") {
-            None => return Err("Failed to split on ': '"),
-            Some((area, shapes)) => (area, shapes),
-        };
-        let (width, length) = match area.split_once('x') {
-            None => return Err("Failed to split area on 'x'"),
-            Some((width, length)) => (width.parse::<usize>(), length.parse::<usize>()),
-        };
-        let width = match width {
-            Ok(w) => w,
-            Err(_) => return Err("Failed to parse width."),
-        };
-        let length = match length {
-            Ok(l) => l,
-            Err(_) => return Err("Failed to parse length."),
-        };
-        let shapes: Vec<_> = shapes
-            .split(' ')
-            .map(|x| x.parse::<usize>().expect("Failed to parse shape."))
-            .collect();
-
-        if shapes.len() != 6 {
-            Err("Not enough shape values to make a Region.")
-        } else {
-            Ok(Self {
-                width,
-                length,
-                shapes0: shapes[0],
-                shapes1: shapes[1],
-                shapes2: shapes[2],
-                shapes3: shapes[3],
-                shapes4: shapes[4],
-                shapes5: shapes[5],
-            })
-        }
-    }
+/// Parser for a Region line like `12x5: 1 0 1 0 2 2`, the width and length
+/// then how many of each shape.
+fn region<'a>() -> impl Fn(&'a str) -> Parsed<'a, Region> {
+    let area = separated_pair(integer(), tag("x"), integer());
+    try_map(
+        key_value(area, integers(" ")),
+        |((width, length), shapes): ((usize, usize), Vec<usize>)| {
+            if shapes.len() == 6 {
+                Ok(Region::new(width, length, shapes))
+            } else {
+                Err("6 shape quantities")
+            }
+        },
+    )
 }
 
 /// Structure representing the various shapes/presents.
@@ -114,53 +94,27 @@ impl Shape {
     }
 }
 
-/// Parse the incoming file to HashMap of Device keys with Devices values.
+/// Parse the incoming file to HashMap of shape index keys with Shape values,
+/// and the Regions.
 fn parse_text(string: &String) -> (HashMap<u8, Shape>, Vec<Region>) {
-    let mut regions = Vec::new();
-    let mut shape_index = 0;
-    let mut shape = Shape::default();
-    let mut shapes: HashMap<u8, Shape> = HashMap::with_capacity(6);
-    let mut shape_line_start = 0;
-
-    for (line_num, line) in string.lines().enumerate() {
-        // "Regions" contain 'x' and "Shapes" do not.
-        if line.contains('x') {
-            regions.push(Region::try_from(line).expect("Failed to get Region from line: {line}"));
-        }
-        // "Shape" indexes are just 2 characters. Update shape_index when we hit one.
-        if line.len() == 2 {
-            shape_index = line
-                .strip_suffix(':')
-                .expect("Failed to strip : from shape index")
-                .parse()
-                .expect("Failed to parse shape index");
-            // Make a new shape in preparation to fill it.
-            shape = Shape::default();
-            // Set the shape_line_start to the next line number.
-            shape_line_start = line_num + 1;
-        }
-        // Shapes have `#` or `.`.
-        if line.contains('#') || line.contains('.') {
-            // Shape y values are relative to top of the shape.
-            let y = line_num - shape_line_start;
-            for (x, c) in line.chars().enumerate() {
-                if c == '#' {
-                    shape.map.insert(coord!(x as i32, y as i32), true);
-                } else if c == '.' {
-                    shape.map.insert(coord!(x as i32, y as i32), false);
-                } else {
-                    panic!("Unrecognized character on line: {line}");
+    // Shapes are an index and colon, then rows of `#` and `.` below it.
+    let rows = lines(take_while1(|c| c == '#' || c == '.', "a row of # and ."));
+    let shape = pair(
+        terminated(integer(), pair(tag(":"), line_end())),
+        map(rows, |rows| {
+            let mut shape = Shape::default();
+            for (y, row) in rows.iter().enumerate() {
+                for (x, c) in row.chars().enumerate() {
+                    shape.map.insert(coord!(x as i32, y as i32), c == '#');
                 }
             }
-        }
-        // Empty line, insert shape to HashMap if it's not default and clear it.
-        if line.is_empty() {
-            if shape != Shape::default() {
-                shapes.insert(shape_index, shape.clone());
-                shape = Shape::default();
-            }
-        }
-    }
+            shape
+        }),
+    );
+    // The shapes each have a blank line after, then it's the regions.
+    let farm = separated_pair(sections(shape), blank_line(), lines(region()));
+    let (shapes, regions) = parse::all(farm, string).unwrap_or_else(|error| panic!("{error}"));
+    let shapes: HashMap<u8, Shape> = shapes.into_iter().collect();
 
     assert_eq!(shapes.len(), 6, "parse_text didn't find correct # shapes.");
 
@@ -198,13 +152,13 @@ fn rectangle_check(region: &Region) -> bool {
 fn part1(file_name: &str) -> usize {
     let file_contents = std::fs::read_to_string(file_name).expect("Couldn't open file");
     let (shapes, regions) = parse_text(&file_contents);
-    println!("regions.len: {}", regions.len());
+    trace!(Info, "regions.len: {}", regions.len());
     let areas_filtered: Vec<_> = regions
         .iter()
         .filter(|&r| shapes_fit_in_area(r, &shapes))
         .collect();
     let areas_filtered_len = areas_filtered.len();
-    println!("areas_filtered.len: {}", areas_filtered_len);
+    trace!(Info, "areas_filtered.len: {}", areas_filtered_len);
 
     let rectangles_filtered: Vec<_> = areas_filtered
         .iter()
@@ -213,13 +167,19 @@ fn part1(file_name: &str) -> usize {
     // My brain broke the first time this ran and it returned 0. Thought I had an
     // error with my code. But no, after the area filter, all the other shapes
     // could easily fit.
-    println!("rectangles_filtered.len: {}", rectangles_filtered.len());
+    trace!(
+        Info,
+        "rectangles_filtered.len: {}",
+        rectangles_filtered.len()
+    );
 
     areas_filtered_len
 }
 
 /// Main function / code entry point.
 fn main() {
+    utilities::trace::init_from_args(std::env::args().skip(1));
+
     println!("Sum for example1: {}", part1("example1.txt"));
     println!("Sum for input: {}", part1("input.txt"));
 }
@@ -249,6 +209,36 @@ mod tests {
         let _ = Region::new(5, 5, vec![0, 1, 2, 3, 4]);
     }
 
+    /// Six shapes then two regions.
+    const FARM: &str = "0:\n###\n##.\n##.\n\n1:\n###\n##.\n.##\n\n2:\n.##\n###\n##.\n\n\
+                        3:\n##.\n###\n##.\n\n4:\n###\n#..\n###\n\n5:\n###\n.#.\n###\n\n\
+                        4x4: 0 0 0 0 2 0\n12x5: 1 0 1 0 2 2\n";
+
+    #[test]
+    fn test_parse_text() {
+        let (shapes, regions) = parse_text(&FARM.to_string());
+        assert_eq!(shapes[&0].area(), 7);
+        assert_eq!(shapes[&5].area(), 7);
+        assert!(!shapes[&0].map[&coord!(2, 1)]);
+        assert_eq!(
+            regions,
+            [
+                Region::new(4, 4, vec![0, 0, 0, 0, 2, 0]),
+                Region::new(12, 5, vec![1, 0, 1, 0, 2, 2])
+            ]
+        );
+        // The same farm with Windows line endings.
+        let (crlf_shapes, crlf_regions) = parse_text(&FARM.replace('\n', "\r\n"));
+        assert_eq!(crlf_shapes, shapes);
+        assert_eq!(crlf_regions, regions);
+    }
+
+    #[test]
+    #[should_panic(expected = "line 32, column 1: expected 6 shape quantities")]
+    fn test_parse_text_short_region() {
+        parse_text(&FARM.replace("12x5: 1 0 1 0 2 2", "12x5: 1 0 1 0 2"));
+    }
+
     #[test]
     fn test_region_area() {
         let region = Region::new(5, 5, vec![0, 1, 2, 3, 4, 5]);
@@ -0,0 +1,352 @@
+//! Module for counting past what fits in a u64. Only does what AoC counting
+//! needs (add, multiply and printing), not a full big number library.
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Mul},
+};
+
+/// Unsigned integer of any size.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct BigUint {
+    /// Base 2^32 digits, least significant first, with no zeros on the end so
+    /// every number has one representation. Zero is empty.
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    /// Make a BigUint of zero.
+    pub const fn zero() -> Self {
+        Self { limbs: Vec::new() }
+    }
+
+    /// Is the number zero.
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// Drop the zero limbs off the end.
+    fn trim(mut self) -> Self {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        self
+    }
+
+    /// Returns the number as a u128 if it fits.
+    pub fn to_u128(&self) -> Option<u128> {
+        if self.limbs.len() > 4 {
+            return None;
+        }
+        Some(
+            self.limbs
+                .iter()
+                .rev()
+                .fold(0u128, |acc, &limb| (acc << 32) | u128::from(limb)),
+        )
+    }
+
+    /// Number of bits needed to write the number, so 0 for zero.
+    pub fn bits(&self) -> u64 {
+        match self.limbs.last() {
+            Some(last) => self.limbs.len() as u64 * 32 - u64::from(last.leading_zeros()),
+            None => 0,
+        }
+    }
+
+    /// Returns the number as a u64 if it fits.
+    pub fn to_u64(&self) -> Option<u64> {
+        self.to_u128().and_then(|x| u64::try_from(x).ok())
+    }
+
+    /// Divide in place by a small number, returning the remainder.
+    fn div_rem_small(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for limb in self.limbs.iter_mut().rev() {
+            let value = (remainder << 32) | u64::from(*limb);
+            *limb = (value / u64::from(divisor)) as u32;
+            remainder = value % u64::from(divisor);
+        }
+        *self = std::mem::take(self).trim();
+        remainder as u32
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(mut value: u128) -> Self {
+        let mut limbs = Vec::new();
+        while value > 0 {
+            limbs.push(value as u32);
+            value >>= 32;
+        }
+        Self { limbs }
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> Self {
+        Self::from(u128::from(value))
+    }
+}
+
+impl Add<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    /// Add two numbers with carrying, like on paper.
+    fn add(self, other: &BigUint) -> BigUint {
+        let (long, short) = if self.limbs.len() >= other.limbs.len() {
+            (&self.limbs, &other.limbs)
+        } else {
+            (&other.limbs, &self.limbs)
+        };
+        let mut limbs = Vec::with_capacity(long.len() + 1);
+        let mut carry = 0u64;
+        for (i, &limb) in long.iter().enumerate() {
+            let sum = u64::from(limb) + u64::from(*short.get(i).unwrap_or(&0)) + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        if carry > 0 {
+            limbs.push(carry as u32);
+        }
+        BigUint { limbs }
+    }
+}
+
+impl AddAssign<&BigUint> for BigUint {
+    fn add_assign(&mut self, other: &BigUint) {
+        *self = &*self + other;
+    }
+}
+
+impl Mul<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    /// Long multiplication, like on paper.
+    fn mul(self, other: &BigUint) -> BigUint {
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                // Can't overflow: (2^32-1)^2 + 2 * (2^32-1) < 2^64.
+                let product = u64::from(a) * u64::from(b) + u64::from(limbs[i + j]) + carry;
+                limbs[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        BigUint { limbs }.trim()
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigUint {
+    /// Print in decimal, working out 9 digits at a time.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        let mut number = self.clone();
+        let mut chunks = Vec::new();
+        while !number.is_zero() {
+            chunks.push(number.div_rem_small(1_000_000_000));
+        }
+        let mut chunks = chunks.into_iter().rev();
+        write!(f, "{}", chunks.next().unwrap_or(0))?;
+        for chunk in chunks {
+            write!(f, "{chunk:09}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A count that stays a plain u64 until it would overflow, then switches to a
+/// BigUint. Always uses the u64 when the value fits, so equal counts compare
+/// equal.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Count {
+    Small(u64),
+    Big(BigUint),
+}
+
+impl Count {
+    /// Make a Count from a BigUint, using the u64 if it fits.
+    fn from_big(big: BigUint) -> Self {
+        match big.to_u64() {
+            Some(small) => Self::Small(small),
+            None => Self::Big(big),
+        }
+    }
+
+    /// Returns the count as a BigUint.
+    pub fn to_big(&self) -> BigUint {
+        match self {
+            Self::Small(small) => BigUint::from(*small),
+            Self::Big(big) => big.clone(),
+        }
+    }
+
+    /// Number of bits needed to write the count, so 0 for zero.
+    pub fn bits(&self) -> u64 {
+        match self {
+            Self::Small(small) => u64::from(u64::BITS - small.leading_zeros()),
+            Self::Big(big) => big.bits(),
+        }
+    }
+
+    /// Returns the count as a u64 if it fits.
+    pub fn to_u64(&self) -> Option<u64> {
+        match self {
+            Self::Small(small) => Some(*small),
+            Self::Big(_) => None,
+        }
+    }
+}
+
+impl Default for Count {
+    fn default() -> Self {
+        Self::Small(0)
+    }
+}
+
+impl From<u64> for Count {
+    fn from(value: u64) -> Self {
+        Self::Small(value)
+    }
+}
+
+impl Add<&Count> for &Count {
+    type Output = Count;
+
+    fn add(self, other: &Count) -> Count {
+        if let (Count::Small(a), Count::Small(b)) = (self, other)
+            && let Some(sum) = a.checked_add(*b)
+        {
+            return Count::Small(sum);
+        }
+        Count::Big(&self.to_big() + &other.to_big())
+    }
+}
+
+impl AddAssign<&Count> for Count {
+    fn add_assign(&mut self, other: &Count) {
+        *self = &*self + other;
+    }
+}
+
+impl Mul<&Count> for &Count {
+    type Output = Count;
+
+    fn mul(self, other: &Count) -> Count {
+        if let (Count::Small(a), Count::Small(b)) = (self, other)
+            && let Some(product) = a.checked_mul(*b)
+        {
+            return Count::Small(product);
+        }
+        Count::from_big(&self.to_big() * &other.to_big())
+    }
+}
+
+impl<'a> Sum<&'a Count> for Count {
+    fn sum<I: Iterator<Item = &'a Count>>(iter: I) -> Self {
+        iter.fold(Count::default(), |acc, x| &acc + x)
+    }
+}
+
+impl Ord for Count {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Small(a), Self::Small(b)) => a.cmp(b),
+            (Self::Small(_), Self::Big(_)) => Ordering::Less,
+            (Self::Big(_), Self::Small(_)) => Ordering::Greater,
+            (Self::Big(a), Self::Big(b)) => a.cmp(b),
+        }
+    }
+}
+
+impl PartialOrd for Count {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Small(small) => write!(f, "{small}"),
+            Self::Big(big) => write!(f, "{big}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_biguint_round_trip() {
+        for value in [
+            0,
+            1,
+            u128::from(u32::MAX) + 1,
+            u128::from(u64::MAX),
+            u128::MAX,
+        ] {
+            let big = BigUint::from(value);
+            assert_eq!(big.to_u128(), Some(value));
+            assert_eq!(big.to_string(), value.to_string());
+        }
+        assert_eq!(BigUint::from(0u64), BigUint::zero());
+    }
+
+    #[test]
+    fn test_biguint_arithmetic() {
+        let max = BigUint::from(u128::MAX);
+        let one = BigUint::from(1u64);
+        let sum = &max + &one;
+        assert_eq!(sum.to_u128(), None);
+        assert_eq!(sum.to_string(), "340282366920938463463374607431768211456");
+        let square = &max * &max;
+        assert_eq!(
+            square.to_string(),
+            "115792089237316195423570985008687907852589419931798687112530834793049593217025"
+        );
+        assert_eq!(&max * &BigUint::zero(), BigUint::zero());
+        assert!(square > sum && sum > max && max > one);
+        assert_eq!(sum.bits(), 129);
+        assert_eq!(one.bits(), 1);
+        assert_eq!(BigUint::zero().bits(), 0);
+    }
+
+    #[test]
+    fn test_count_switches_to_big() {
+        let max = Count::from(u64::MAX);
+        let one = Count::from(1);
+        assert_eq!(&one + &one, Count::Small(2));
+        let sum = &max + &one;
+        assert!(matches!(sum, Count::Big(_)));
+        assert_eq!(sum.to_string(), "18446744073709551616");
+        assert_eq!(sum.to_u64(), None);
+        assert_eq!(&sum * &Count::from(0), Count::Small(0));
+        assert_eq!([max.clone(), one].iter().sum::<Count>(), sum);
+        assert!(sum > max);
+        assert_eq!(sum.bits(), 65);
+        assert_eq!(max.bits(), 64);
+    }
+}
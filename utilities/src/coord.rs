@@ -5,27 +5,91 @@ use std::{
     collections::HashSet,
     fmt,
     hash::Hash,
-    ops::{Add, Sub},
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
 };
 
-/// Structure representing a coordinate in 2D space.
+/// Signed integer types a `Coord` can be made of.
+pub trait Number:
+    Copy
+    + Ord
+    + Hash
+    + fmt::Debug
+    + fmt::Display
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Neg<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn abs(self) -> Self;
+    fn signum(self) -> Self;
+    /// Convert to an i128, which holds every value of the types used. Sums
+    /// and products of the results can still overflow an i128.
+    fn to_i128(self) -> i128;
+}
+
+macro_rules! impl_number {
+    ($($t:ty),+) => {
+        $(impl Number for $t {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+
+            fn checked_add(self, other: Self) -> Option<Self> {
+                <$t>::checked_add(self, other)
+            }
+
+            fn checked_sub(self, other: Self) -> Option<Self> {
+                <$t>::checked_sub(self, other)
+            }
+
+            fn checked_mul(self, other: Self) -> Option<Self> {
+                <$t>::checked_mul(self, other)
+            }
+
+            fn abs(self) -> Self {
+                <$t>::abs(self)
+            }
+
+            fn signum(self) -> Self {
+                <$t>::signum(self)
+            }
+
+            fn to_i128(self) -> i128 {
+                self as i128
+            }
+        })+
+    };
+}
+
+impl_number!(i8, i16, i32, i64, i128, isize);
+
+/// Structure representing a coordinate in 2D space. Defaults to i32s, which
+/// fit most grids; use `Coord<i64>` when the numbers get bigger.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub struct Coord {
+pub struct Coord<T = i32> {
     /// X coordinate.
-    pub x: i32,
+    pub x: T,
     /// Y coordinate.
-    pub y: i32,
+    pub y: T,
 }
 
-/// Create a new coordinate with the given components.
+/// Create a new coordinate with the given components. The type of the
+/// components picks the type of the coordinate.
 /// ```rust
 /// # use utilities::coord;
 /// coord!(1, 2);
+/// coord!(1_i64, 2);
 /// ```
 /// The above will expand to:
 /// ```rust
 /// # use utilities::Coord;
 /// Coord::new(1, 2);
+/// Coord::new(1_i64, 2);
 /// ````
 #[macro_export]
 macro_rules! coord {
@@ -34,55 +98,58 @@ macro_rules! coord {
     };
 }
 
-impl Coord {
+impl<T> Coord<T> {
     /// Make a new Coord from x and y coordinates.
-    pub const fn new(x: i32, y: i32) -> Self {
+    pub const fn new(x: T, y: T) -> Self {
         Self { x: x, y: y }
     }
+}
 
+impl<T: Number> Coord<T> {
     /// Return a cordinate north/up from this coordinate.
-    pub const fn north(&self) -> Self {
-        Self::new(self.x, self.y - 1)
+    pub fn north(&self) -> Self {
+        Self::new(self.x, self.y - T::ONE)
     }
 
     /// Return a cordinate northeast/up-right from this coordinate.
-    pub const fn northeast(&self) -> Self {
-        Self::new(self.x + 1, self.y - 1)
+    pub fn northeast(&self) -> Self {
+        Self::new(self.x + T::ONE, self.y - T::ONE)
     }
 
     /// Return a cordinate east/right from this coordinate.
-    pub const fn east(&self) -> Self {
-        Self::new(self.x + 1, self.y)
+    pub fn east(&self) -> Self {
+        Self::new(self.x + T::ONE, self.y)
     }
 
     /// Return a cordinate southeast/down-right from this coordinate.
-    pub const fn southeast(&self) -> Self {
-        Self::new(self.x + 1, self.y + 1)
+    pub fn southeast(&self) -> Self {
+        Self::new(self.x + T::ONE, self.y + T::ONE)
     }
 
     /// Return a cordinate south/down from this coordinate.
-    pub const fn south(&self) -> Self {
-        Self::new(self.x, self.y + 1)
+    pub fn south(&self) -> Self {
+        Self::new(self.x, self.y + T::ONE)
     }
 
     /// Return a cordinate southwest/down-left from this coordinate.
-    pub const fn southwest(&self) -> Self {
-        Self::new(self.x - 1, self.y + 1)
+    pub fn southwest(&self) -> Self {
+        Self::new(self.x - T::ONE, self.y + T::ONE)
     }
 
     /// Return a cordinate west/left from this coordinate.
-    pub const fn west(&self) -> Self {
-        Self::new(self.x - 1, self.y)
+    pub fn west(&self) -> Self {
+        Self::new(self.x - T::ONE, self.y)
     }
 
     /// Return a cordinate northwest/up-left from this coordinate.
-    pub const fn northwest(&self) -> Self {
-        Self::new(self.x - 1, self.y - 1)
+    pub fn northwest(&self) -> Self {
+        Self::new(self.x - T::ONE, self.y - T::ONE)
     }
 
-    /// Get surrounding coordinates.
-    pub fn get_surrounding_coords(&self) -> HashSet<Coord> {
-        HashSet::from([
+    /// Get the 8 surrounding coordinates, clockwise from north, without
+    /// allocating.
+    pub fn surrounding(&self) -> [Self; 8] {
+        [
             self.north(),
             self.northeast(),
             self.east(),
@@ -91,27 +158,189 @@ impl Coord {
             self.southwest(),
             self.west(),
             self.northwest(),
-        ])
+        ]
+    }
+
+    /// Get surrounding coordinates.
+    pub fn get_surrounding_coords(&self) -> HashSet<Self> {
+        HashSet::from(self.surrounding())
     }
 
     /// Partially get the distance between two points. If you square root the result
     /// it will be the complete distance. Done this way since usually don't care
     /// about the absolute distance and just need the relative.
-    pub fn distance_between(&self, other: &Self) -> i64 {
-        // Convert to i64's otherwise the power can multiply into overflow.
-        (i64::from(self.x) - i64::from(other.x)).pow(2)
-            + (i64::from(self.y) - i64::from(other.y)).pow(2)
+    ///
+    /// Fits whenever the gaps along x and y are both under 2^63, which covers
+    /// every pair of i32 coordinates. Panics if it doesn't fit in an i128, see
+    /// `checked_distance_between` for anything bigger.
+    pub fn distance_between(&self, other: &Self) -> i128 {
+        self.checked_distance_between(other)
+            .and_then(|distance| i128::try_from(distance).ok())
+            .expect("Coord::distance_between overflowed an i128.")
+    }
+
+    /// The same as `distance_between`, but None if it doesn't fit in a u128
+    /// rather than panicking.
+    pub fn checked_distance_between(&self, other: &Self) -> Option<u128> {
+        let dx = self.x.to_i128().abs_diff(other.x.to_i128());
+        let dy = self.y.to_i128().abs_diff(other.y.to_i128());
+        dx.checked_mul(dx)?.checked_add(dy.checked_mul(dy)?)
+    }
+
+    /// Distance walking only along x and y, like a taxi on a grid.
+    pub fn manhattan(&self, other: &Self) -> T {
+        let gap = (*self - *other).abs();
+        gap.x + gap.y
+    }
+
+    /// Distance moving in any of the 8 directions, like a king in chess.
+    pub fn chebyshev(&self, other: &Self) -> T {
+        let gap = (*self - *other).abs();
+        gap.x.max(gap.y)
+    }
+
+    /// Dot product, treating both coordinates as vectors.
+    pub fn dot(&self, other: &Self) -> T {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The 2D cross product, treating both coordinates as vectors. Positive
+    /// when `other` is clockwise of this on a grid with y going down, and
+    /// zero when they're in a line.
+    pub fn cross(&self, other: &Self) -> T {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// Both parts made positive.
+    pub fn abs(&self) -> Self {
+        Self::new(self.x.abs(), self.y.abs())
+    }
+
+    /// Both parts as -1, 0 or 1, which is a step towards this from the origin.
+    pub fn signum(&self) -> Self {
+        Self::new(self.x.signum(), self.y.signum())
+    }
+
+    /// Add two coordinates, or None if either part overflows.
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(Self::new(
+            self.x.checked_add(other.x)?,
+            self.y.checked_add(other.y)?,
+        ))
+    }
+
+    /// Subtract two coordinates, or None if either part overflows.
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        Some(Self::new(
+            self.x.checked_sub(other.x)?,
+            self.y.checked_sub(other.y)?,
+        ))
+    }
+
+    /// Convert to a coordinate of another integer type, or None if either
+    /// part doesn't fit.
+    pub fn try_cast<U: TryFrom<T>>(&self) -> Option<Coord<U>> {
+        Some(Coord::new(
+            U::try_from(self.x).ok()?,
+            U::try_from(self.y).ok()?,
+        ))
     }
 }
 
-impl fmt::Display for Coord {
+/// One of the four directions you can move on a grid.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    /// All directions going clockwise from north.
+    pub const ALL: [Self; 4] = [Self::North, Self::East, Self::South, Self::West];
+
+    /// Return the direction after a quarter turn anticlockwise.
+    pub const fn turn_left(self) -> Self {
+        match self {
+            Self::North => Self::West,
+            Self::East => Self::North,
+            Self::South => Self::East,
+            Self::West => Self::South,
+        }
+    }
+
+    /// Return the direction after a quarter turn clockwise.
+    pub const fn turn_right(self) -> Self {
+        match self {
+            Self::North => Self::East,
+            Self::East => Self::South,
+            Self::South => Self::West,
+            Self::West => Self::North,
+        }
+    }
+
+    /// Return the direction facing the other way.
+    pub const fn opposite(self) -> Self {
+        self.turn_left().turn_left()
+    }
+}
+
+impl TryFrom<&str> for Direction {
+    type Error = &'static str;
+
+    /// Parse a direction from its name or first letter, e.g. "south" or "S".
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_ascii_lowercase().as_str() {
+            "n" | "north" | "up" => Ok(Self::North),
+            "e" | "east" | "right" => Ok(Self::East),
+            "s" | "south" | "down" => Ok(Self::South),
+            "w" | "west" | "left" => Ok(Self::West),
+            _ => Err("Direction must be north, east, south or west"),
+        }
+    }
+}
+
+impl<T: Number> Coord<T> {
+    /// Return the coordinate one step away in the given direction.
+    pub fn step(&self, direction: Direction) -> Self {
+        match direction {
+            Direction::North => self.north(),
+            Direction::East => self.east(),
+            Direction::South => self.south(),
+            Direction::West => self.west(),
+        }
+    }
+
+    /// Return the coordinate one step away in the given direction, or None if
+    /// that goes past the edge of the integer type.
+    pub fn checked_step(&self, direction: Direction) -> Option<Self> {
+        let (zero, one) = (T::ZERO, T::ONE);
+        let offset = match direction {
+            Direction::North => Self::new(zero, zero.checked_sub(one)?),
+            Direction::East => Self::new(one, zero),
+            Direction::South => Self::new(zero, one),
+            Direction::West => Self::new(zero.checked_sub(one)?, zero),
+        };
+        self.checked_add(&offset)
+    }
+}
+
+impl From<Coord<i32>> for Coord<i64> {
+    /// Widen a coordinate, which always fits.
+    fn from(coord: Coord<i32>) -> Self {
+        Self::new(coord.x.into(), coord.y.into())
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Coord<T> {
     /// Format the coordinate to print out nicely.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "({}, {})", self.x, self.y)
     }
 }
 
-impl Add for Coord {
+impl<T: Number> Add for Coord<T> {
     type Output = Self;
 
     /// Add two coordinates together to make a third. Useful for offsetting a distance.
@@ -120,7 +349,7 @@ impl Add for Coord {
     }
 }
 
-impl Sub for Coord {
+impl<T: Number> Sub for Coord<T> {
     type Output = Self;
 
     /// Subtract two coordinates to see the x,y distance between them.
@@ -129,6 +358,36 @@ impl Sub for Coord {
     }
 }
 
+impl<T: Number> AddAssign for Coord<T> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Number> SubAssign for Coord<T> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl<T: Number> Mul<T> for Coord<T> {
+    type Output = Self;
+
+    /// Scale both parts by the same amount.
+    fn mul(self, scale: T) -> Self {
+        Self::new(self.x * scale, self.y * scale)
+    }
+}
+
+impl<T: Number> Neg for Coord<T> {
+    type Output = Self;
+
+    /// Point the other way.
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -170,4 +429,129 @@ mod tests {
         let coord2 = Coord::new(1, 1);
         assert_eq!(coord - coord2, Coord::new(0, 0));
     }
+
+    #[test]
+    fn test_direction_turns() {
+        assert_eq!(Direction::North.turn_right(), Direction::East);
+        assert_eq!(Direction::North.turn_left(), Direction::West);
+        assert_eq!(Direction::East.opposite(), Direction::West);
+        for direction in Direction::ALL {
+            assert_eq!(direction.turn_left().turn_right(), direction);
+            assert_eq!(direction.opposite().opposite(), direction);
+        }
+        assert_eq!(Direction::try_from("S"), Ok(Direction::South));
+        assert_eq!(Direction::try_from("west"), Ok(Direction::West));
+        assert!(Direction::try_from("sideways").is_err());
+    }
+
+    #[test]
+    fn test_step() {
+        let coord = Coord::new(1, 1);
+        assert_eq!(coord.step(Direction::North), Coord::new(1, 0));
+        assert_eq!(coord.step(Direction::East), Coord::new(2, 1));
+        assert_eq!(coord.step(Direction::South), Coord::new(1, 2));
+        assert_eq!(coord.step(Direction::West), Coord::new(0, 1));
+    }
+
+    #[test]
+    fn test_i64_coords() {
+        let big = coord!(5_000_000_000_i64, -3);
+        assert_eq!(big.east(), coord!(5_000_000_001, -3));
+        assert_eq!(big + coord!(1, 1), coord!(5_000_000_001, -2));
+        assert_eq!(big.to_string(), "(5000000000, -3)");
+        assert_eq!(big.step(Direction::North), coord!(5_000_000_000, -4));
+        assert_eq!(
+            coord!(0_i64, 0).distance_between(&coord!(3_000_000_000, 4_000_000_000)),
+            25_000_000_000_000_000_000
+        );
+        assert_eq!(Coord::<i64>::from(coord!(1, 2)), coord!(1_i64, 2));
+        assert_eq!(big.try_cast::<i32>(), None);
+        assert_eq!(coord!(7_i64, -2).try_cast::<i32>(), Some(coord!(7, -2)));
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let edge = coord!(i32::MAX, 0);
+        assert_eq!(edge.checked_add(&coord!(1, 0)), None);
+        assert_eq!(
+            edge.checked_sub(&coord!(1, 0)),
+            Some(coord!(i32::MAX - 1, 0))
+        );
+        assert_eq!(edge.checked_step(Direction::East), None);
+        assert_eq!(
+            edge.checked_step(Direction::South),
+            Some(coord!(i32::MAX, 1))
+        );
+        let corner = coord!(i8::MIN, i8::MIN);
+        assert_eq!(corner.checked_step(Direction::North), None);
+        assert_eq!(corner.checked_step(Direction::West), None);
+        assert_eq!(
+            corner.checked_step(Direction::East),
+            Some(coord!(i8::MIN + 1, i8::MIN))
+        );
+        assert_eq!(
+            coord!(i32::MIN, i32::MIN).distance_between(&coord!(i32::MAX, i32::MAX)),
+            2 * (1_i128 << 32).pow(2) - 4 * (1 << 32) + 2
+        );
+        let (low, high) = (coord!(i64::MIN, 0), coord!(i64::MAX, 0));
+        assert_eq!(
+            low.checked_distance_between(&high),
+            Some(u128::from(u64::MAX).pow(2))
+        );
+        assert_eq!(
+            coord!(i64::MIN, i64::MIN).checked_distance_between(&coord!(i64::MAX, i64::MAX)),
+            None
+        );
+        assert_eq!(
+            coord!(i128::MIN, 0).checked_distance_between(&coord!(i128::MAX, 0)),
+            None
+        );
+        assert_eq!(
+            coord!(0_i128, -3).checked_distance_between(&coord!(4, 0)),
+            Some(25)
+        );
+        let gap = (1_i128 << 63) - 1;
+        assert_eq!(
+            coord!(0, 0).distance_between(&coord!(gap, gap)),
+            2 * gap * gap
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Coord::distance_between overflowed an i128.")]
+    fn test_distance_between_overflow() {
+        let _ = coord!(i64::MIN, 0).distance_between(&coord!(i64::MAX, 0));
+    }
+
+    #[test]
+    fn test_metrics() {
+        let (a, b) = (coord!(1, 2), coord!(4, -2));
+        assert_eq!(a.manhattan(&b), 7);
+        assert_eq!(a.chebyshev(&b), 4);
+        assert_eq!(a.distance_between(&b), 25);
+        assert_eq!(b.manhattan(&a), a.manhattan(&b));
+        assert_eq!(a.manhattan(&a), 0);
+    }
+
+    #[test]
+    fn test_vector_ops() {
+        let (east, south) = (coord!(1, 0), coord!(0, 1));
+        assert_eq!(east.dot(&south), 0);
+        assert_eq!(coord!(2, 3).dot(&coord!(4, 5)), 23);
+        // South is a quarter turn clockwise from east on a grid.
+        assert_eq!(east.cross(&south), 1);
+        assert_eq!(south.cross(&east), -1);
+        assert_eq!(coord!(2, 4).cross(&coord!(1, 2)), 0);
+
+        assert_eq!(coord!(2, -3) * 3, coord!(6, -9));
+        assert_eq!(-coord!(2, -3), coord!(-2, 3));
+        assert_eq!(coord!(-2, 3).abs(), coord!(2, 3));
+        assert_eq!(coord!(-7, 0).signum(), coord!(-1, 0));
+
+        let mut walker = coord!(0_i64, 0);
+        walker += coord!(3, 4);
+        walker -= coord!(1, 1);
+        assert_eq!(walker, coord!(2, 3));
+        assert_eq!(walker * 2 - walker, walker);
+    }
 }
@@ -0,0 +1,621 @@
+//! Module for polygons with corners on whole coordinates, like a loop of
+//! tiles on a grid. Everything is worked out with integers, so there are no
+//! rounding problems.
+//!
+//! The y axis goes down like on a grid, so clockwise means clockwise as it
+//! looks when printed out.
+
+use std::{collections::BTreeSet, fmt};
+
+use crate::{Coord, coord};
+
+/// Whether a loop goes clockwise or anticlockwise.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Orientation {
+    Clockwise,
+    Anticlockwise,
+}
+
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Clockwise => write!(f, "clockwise"),
+            Self::Anticlockwise => write!(f, "anticlockwise"),
+        }
+    }
+}
+
+/// Where a point is compared to a polygon.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Location {
+    Inside,
+    /// On one of the edges, including the corners.
+    Boundary,
+    Outside,
+}
+
+/// A point with both coordinates doubled, so halfway points are whole too.
+/// Kept in i128 so cross products of differences between them can't overflow.
+type Doubled = Coord<i128>;
+
+/// The point in i128, which products of two differences always fit in.
+fn widened(coord: &Coord) -> Coord<i128> {
+    coord!(i128::from(coord.x), i128::from(coord.y))
+}
+
+fn doubled(coord: &Coord) -> Doubled {
+    widened(coord) * 2
+}
+
+/// The point halfway between two doubled points. Only whole if they're an
+/// even distance apart, which points from whole coordinates always are.
+fn halfway(a: Doubled, b: Doubled) -> Doubled {
+    coord!((a.x + b.x) / 2, (a.y + b.y) / 2)
+}
+
+/// Cross product of `a - origin` and `b - origin`. Positive when `b` is
+/// clockwise of `a` seen from `origin`, zero when all three are on a line.
+fn cross(origin: Doubled, a: Doubled, b: Doubled) -> i128 {
+    (a - origin).cross(&(b - origin))
+}
+
+/// Is `point` on the segment from `a` to `b`, ends included.
+fn on_segment(a: Doubled, b: Doubled, point: Doubled) -> bool {
+    cross(a, b, point) == 0
+        && point.x >= a.x.min(b.x)
+        && point.x <= a.x.max(b.x)
+        && point.y >= a.y.min(b.y)
+        && point.y <= a.y.max(b.y)
+}
+
+/// The smallest and largest x and y over all the points, as the top left and
+/// bottom right corners. None if there are no points.
+pub fn bounds<'a>(points: impl IntoIterator<Item = &'a Coord>) -> Option<(Coord, Coord)> {
+    points.into_iter().fold(None, |bounds, point| match bounds {
+        None => Some((*point, *point)),
+        Some((min, max)) => Some((
+            Coord::new(min.x.min(point.x), min.y.min(point.y)),
+            Coord::new(max.x.max(point.x), max.y.max(point.y)),
+        )),
+    })
+}
+
+/// How many tiles are in the rectangle with opposite corners on these two
+/// tiles, counting the corners themselves. A u128 as both sides can be 2^32
+/// tiles long.
+pub fn tile_area(a: &Coord, b: &Coord) -> u128 {
+    let width = u128::from(a.x.abs_diff(b.x)) + 1;
+    let height = u128::from(a.y.abs_diff(b.y)) + 1;
+    width * height
+}
+
+/// A straight line between two points.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Segment {
+    pub start: Coord,
+    pub end: Coord,
+}
+
+impl Segment {
+    pub const fn new(start: Coord, end: Coord) -> Self {
+        Self { start, end }
+    }
+
+    /// Is the segment only along x or only along y.
+    pub const fn is_axis_aligned(&self) -> bool {
+        self.start.x == self.end.x || self.start.y == self.end.y
+    }
+
+    /// Is the point on the segment, ends included.
+    pub fn contains(&self, point: &Coord) -> bool {
+        on_segment(doubled(&self.start), doubled(&self.end), doubled(point))
+    }
+
+    /// Do the two segments share any point, including just touching ends or
+    /// overlapping along the same line.
+    pub fn intersects(&self, other: &Self) -> bool {
+        let (a, b) = (doubled(&self.start), doubled(&self.end));
+        let (c, d) = (doubled(&other.start), doubled(&other.end));
+        let sides = |d1: i128, d2: i128| d1.signum() * d2.signum();
+        (sides(cross(a, b, c), cross(a, b, d)) < 0 && sides(cross(c, d, a), cross(c, d, b)) < 0)
+            || on_segment(a, b, c)
+            || on_segment(a, b, d)
+            || on_segment(c, d, a)
+            || on_segment(c, d, b)
+    }
+
+    /// Do the two segments cross over each other at a single point that's
+    /// not the end of either.
+    pub fn crosses(&self, other: &Self) -> bool {
+        let (a, b) = (doubled(&self.start), doubled(&self.end));
+        let (c, d) = (doubled(&other.start), doubled(&other.end));
+        cross(a, b, c).signum() * cross(a, b, d).signum() < 0
+            && cross(c, d, a).signum() * cross(c, d, b).signum() < 0
+    }
+}
+
+/// A closed loop of straight edges, from each corner to the next and from the
+/// last back to the first.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Polygon {
+    vertices: Vec<Coord>,
+}
+
+impl Polygon {
+    /// Make a polygon going through the corners in order.
+    pub fn new(vertices: Vec<Coord>) -> Self {
+        Self { vertices }
+    }
+
+    /// The corners in order.
+    pub fn vertices(&self) -> &[Coord] {
+        &self.vertices
+    }
+
+    /// Every edge in order, ending with the one back to the first corner.
+    pub fn edges(&self) -> impl Iterator<Item = Segment> + '_ {
+        self.vertices
+            .iter()
+            .zip(self.vertices.iter().cycle().skip(1))
+            .map(|(start, end)| Segment::new(*start, *end))
+    }
+
+    /// Top left and bottom right corners of the box around the polygon.
+    pub fn bounds(&self) -> Option<(Coord, Coord)> {
+        bounds(&self.vertices)
+    }
+
+    /// Twice the area from the shoelace formula, positive if the loop goes
+    /// clockwise. Doubled so it's always whole.
+    pub fn signed_area_doubled(&self) -> i128 {
+        self.edges()
+            .map(|edge| widened(&edge.start).cross(&widened(&edge.end)))
+            .sum()
+    }
+
+    /// The area enclosed, treating the corners as points rather than tiles.
+    pub fn area(&self) -> f64 {
+        self.signed_area_doubled().unsigned_abs() as f64 / 2.0
+    }
+
+    /// Which way the loop goes round. None if it encloses no area.
+    pub fn orientation(&self) -> Option<Orientation> {
+        match self.signed_area_doubled().signum() {
+            1 => Some(Orientation::Clockwise),
+            -1 => Some(Orientation::Anticlockwise),
+            _ => None,
+        }
+    }
+
+    /// How many times the loop goes round the doubled point, positive for
+    /// clockwise. Points on the boundary aren't handled, see `locate`.
+    fn winding_number_doubled(&self, point: Doubled) -> i32 {
+        let mut winding = 0;
+        for edge in self.edges() {
+            let (a, b) = (doubled(&edge.start), doubled(&edge.end));
+            if a.y <= point.y {
+                if b.y > point.y && cross(a, b, point) > 0 {
+                    winding += 1;
+                }
+            } else if b.y <= point.y && cross(a, b, point) < 0 {
+                winding -= 1;
+            }
+        }
+        winding
+    }
+
+    /// How many times the loop goes round the point, positive for clockwise.
+    /// Not meaningful for points on the boundary, see `locate`.
+    pub fn winding_number(&self, point: &Coord) -> i32 {
+        self.winding_number_doubled(doubled(point))
+    }
+
+    fn locate_doubled(&self, point: Doubled) -> Location {
+        if self
+            .edges()
+            .any(|edge| on_segment(doubled(&edge.start), doubled(&edge.end), point))
+        {
+            Location::Boundary
+        } else if self.winding_number_doubled(point) != 0 {
+            Location::Inside
+        } else {
+            Location::Outside
+        }
+    }
+
+    /// Is the point inside, outside or on the boundary of the polygon.
+    pub fn locate(&self, point: &Coord) -> Location {
+        self.locate_doubled(doubled(point))
+    }
+
+    /// Is the point inside or on the boundary of the polygon.
+    pub fn contains(&self, point: &Coord) -> bool {
+        self.locate(point) != Location::Outside
+    }
+
+    /// Pairs of edges, by index in `edges`, that meet where they shouldn't.
+    /// Edges next to each other are only allowed to share their common corner.
+    /// Checks every pair, so it's quadratic in the number of corners.
+    pub fn self_intersections(&self) -> Vec<(usize, usize)> {
+        let edges: Vec<Segment> = self.edges().collect();
+        let count = edges.len();
+        let mut found = Vec::new();
+        for i in 0..count {
+            for j in i + 1..count {
+                let (first, second) = (&edges[i], &edges[j]);
+                let meet = if j == i + 1 {
+                    // Doubling back along the edge just walked.
+                    first.contains(&second.end) || second.contains(&first.start)
+                } else if i == 0 && j == count - 1 {
+                    second.contains(&first.end) || first.contains(&second.start)
+                } else {
+                    first.intersects(second)
+                };
+                if meet {
+                    found.push((i, j));
+                }
+            }
+        }
+        found
+    }
+
+    /// A pair of edges that meet where they shouldn't, as in
+    /// `self_intersections`, or None if there aren't any. When every edge is
+    /// along a row or column this sweeps across them in O(n log n) rather
+    /// than checking every pair.
+    pub fn find_self_intersection(&self) -> Option<(usize, usize)> {
+        let edges: Vec<Segment> = self.edges().collect();
+        let count = edges.len();
+        if count <= 3 || !edges.iter().all(Segment::is_axis_aligned) {
+            return self.self_intersections().first().copied();
+        }
+        // Edges next to each other can only meet by doubling back.
+        for i in 0..count {
+            let (first, second) = (&edges[i], &edges[(i + 1) % count]);
+            if first.contains(&second.end) || second.contains(&first.start) {
+                return Some(if i + 1 == count { (0, i) } else { (i, i + 1) });
+            }
+        }
+        let transposed: Vec<Segment> = edges
+            .iter()
+            .map(|edge| {
+                Segment::new(
+                    coord!(edge.start.y, edge.start.x),
+                    coord!(edge.end.y, edge.end.x),
+                )
+            })
+            .collect();
+        sweep_edges(&edges).or_else(|| sweep_edges(&transposed))
+    }
+
+    /// Does the loop never touch or cross itself.
+    pub fn is_simple(&self) -> bool {
+        self.vertices.len() >= 3 && self.find_self_intersection().is_none()
+    }
+
+    /// Is the whole rectangle with opposite corners `a` and `b` inside or on
+    /// the boundary of the polygon.
+    ///
+    /// This treats the polygon as an area rather than tiles, so two edges
+    /// right next to each other still leave a thin gap outside between them.
+    pub fn contains_rect(&self, a: &Coord, b: &Coord) -> bool {
+        let (min, max) = bounds([a, b]).unwrap();
+        let (min, max) = (doubled(&min), doubled(&max));
+        if min.x == max.x || min.y == max.y {
+            return self.contains_line(min, max);
+        }
+
+        // If no edge gets into the rectangle, it's either all in or all out.
+        let corners = [min, coord!(max.x, min.y), max, coord!(min.x, max.y)];
+        let enters = |edge: Segment| {
+            let (start, end) = (doubled(&edge.start), doubled(&edge.end));
+            let beside = start.x.max(end.x) <= min.x
+                || start.x.min(end.x) >= max.x
+                || start.y.max(end.y) <= min.y
+                || start.y.min(end.y) >= max.y;
+            let sides = corners.map(|corner| cross(start, end, corner));
+            !beside && !sides.iter().all(|&s| s >= 0) && !sides.iter().all(|&s| s <= 0)
+        };
+        !self.edges().any(enters) && self.locate_doubled(halfway(min, max)) != Location::Outside
+    }
+
+    /// Is the line between the doubled points inside or on the boundary.
+    fn contains_line(&self, start: Doubled, end: Doubled) -> bool {
+        let line = |edge: &Segment| (doubled(&edge.start), doubled(&edge.end));
+        let crossed = self.edges().any(|edge| {
+            let (a, b) = line(&edge);
+            cross(a, b, start).signum() * cross(a, b, end).signum() < 0
+                && cross(start, end, a).signum() * cross(start, end, b).signum() < 0
+        });
+        if crossed {
+            return false;
+        }
+        // Nothing crosses, so the line can only go in or out at corners on
+        // it. Check each end and each piece between those.
+        let mut stops: Vec<Doubled> = self
+            .vertices
+            .iter()
+            .map(doubled)
+            .filter(|&corner| on_segment(start, end, corner))
+            .chain([start, end])
+            .collect();
+        stops.sort_unstable_by_key(|stop| (stop.x, stop.y));
+        stops.dedup();
+        stops
+            .iter()
+            .all(|&stop| self.locate_doubled(stop) != Location::Outside)
+            && stops
+                .windows(2)
+                .all(|pair| self.locate_doubled(halfway(pair[0], pair[1])) != Location::Outside)
+    }
+}
+
+/// Sweep left to right over a loop's edges, which all have to be along rows
+/// or columns, for two that meet without being next to each other in the
+/// loop. Horizontal edges are kept while the sweep is over them, checked
+/// against the others on the same row as they're added, and each vertical
+/// edge is checked against those in its range of y. Vertical edges along the
+/// same column aren't found, so sweep again with x and y swapped for those.
+fn sweep_edges(edges: &[Segment]) -> Option<(usize, usize)> {
+    let count = edges.len();
+    let adjacent = |i: usize, j: usize| (i + 1) % count == j || (j + 1) % count == i;
+    // Sorting puts edges starting at an x before the vertical edges there,
+    // and edges ending at it after.
+    let mut events: Vec<(i32, u8, usize)> = Vec::with_capacity(2 * count);
+    for (i, edge) in edges.iter().enumerate() {
+        let (left, right) = (edge.start.x.min(edge.end.x), edge.start.x.max(edge.end.x));
+        if edge.start.y == edge.end.y {
+            events.push((left, 0, i));
+            events.push((right, 2, i));
+        } else {
+            events.push((left, 1, i));
+        }
+    }
+    events.sort_unstable();
+
+    // Horizontal edges the sweep is over, by y. Any found in a range of y
+    // meet the edge being checked, and at most two of those are next to it.
+    let mut active: BTreeSet<(i32, usize)> = BTreeSet::new();
+    for (_, event, i) in events {
+        let edge = &edges[i];
+        let (top, bottom) = (edge.start.y.min(edge.end.y), edge.start.y.max(edge.end.y));
+        if event == 2 {
+            active.remove(&(top, i));
+            continue;
+        }
+        let met = active
+            .range((top, 0)..=(bottom, usize::MAX))
+            .map(|&(_, j)| j)
+            .find(|&j| !adjacent(i, j));
+        if let Some(j) = met {
+            return Some((i.min(j), i.max(j)));
+        }
+        if event == 0 {
+            active.insert((top, i));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coord;
+
+    /// A U shape going clockwise, open at the top between x=5 and x=9.
+    fn u_shape() -> Polygon {
+        Polygon::new(vec![
+            coord!(1, 1),
+            coord!(5, 1),
+            coord!(5, 9),
+            coord!(9, 9),
+            coord!(9, 1),
+            coord!(13, 1),
+            coord!(13, 12),
+            coord!(1, 12),
+        ])
+    }
+
+    #[test]
+    fn test_bounds_and_tile_area() {
+        assert_eq!(bounds(&[]), None);
+        let corners = [coord!(9, 7), coord!(2, 5)];
+        assert_eq!(bounds(&corners), Some((coord!(2, 5), coord!(9, 7))));
+        assert_eq!(tile_area(&corners[0], &corners[1]), 24);
+        assert_eq!(tile_area(&coord!(7, 3), &coord!(2, 3)), 6);
+        assert_eq!(
+            tile_area(
+                &coord!(-2_000_000_000, -2_000_000_000),
+                &coord!(2_000_000_000, 2_000_000_000)
+            ),
+            16_000_000_008_000_000_001
+        );
+        assert_eq!(
+            tile_area(&coord!(i32::MIN, i32::MIN), &coord!(i32::MAX, i32::MAX)),
+            1 << 64
+        );
+    }
+
+    #[test]
+    fn test_edges_wrap_round() {
+        let square = Polygon::new(vec![coord!(0, 0), coord!(2, 0), coord!(2, 2), coord!(0, 2)]);
+        let edges: Vec<Segment> = square.edges().collect();
+        assert_eq!(edges.len(), 4);
+        assert_eq!(edges[3], Segment::new(coord!(0, 2), coord!(0, 0)));
+        assert!(edges.iter().all(Segment::is_axis_aligned));
+    }
+
+    #[test]
+    fn test_area_and_orientation() {
+        let u = u_shape();
+        assert_eq!(u.signed_area_doubled(), 2 * (12 * 11 - 4 * 8));
+        assert_eq!(u.area(), 100.0);
+        assert_eq!(u.orientation(), Some(Orientation::Clockwise));
+        let backwards = Polygon::new(u.vertices().iter().rev().copied().collect());
+        assert_eq!(backwards.orientation(), Some(Orientation::Anticlockwise));
+        assert_eq!(backwards.area(), 100.0);
+        let flat = Polygon::new(vec![coord!(0, 0), coord!(5, 0)]);
+        assert_eq!(flat.orientation(), None);
+    }
+
+    #[test]
+    fn test_locate() {
+        let u = u_shape();
+        assert_eq!(u.locate(&coord!(3, 5)), Location::Inside);
+        assert_eq!(u.locate(&coord!(7, 5)), Location::Outside);
+        assert_eq!(u.locate(&coord!(7, 10)), Location::Inside);
+        assert_eq!(u.locate(&coord!(0, 0)), Location::Outside);
+        assert_eq!(u.locate(&coord!(5, 4)), Location::Boundary);
+        assert_eq!(u.locate(&coord!(13, 12)), Location::Boundary);
+        assert_eq!(u.winding_number(&coord!(3, 5)), 1);
+        let backwards = Polygon::new(u.vertices().iter().rev().copied().collect());
+        assert_eq!(backwards.winding_number(&coord!(3, 5)), -1);
+    }
+
+    #[test]
+    fn test_huge_coordinates() {
+        let (low, high) = (-2_000_000_000, 2_000_000_000);
+        let square = Polygon::new(vec![
+            coord!(low, low),
+            coord!(high, low),
+            coord!(high, high),
+            coord!(low, high),
+        ]);
+        assert_eq!(square.locate(&coord!(5, 5)), Location::Inside);
+        assert_eq!(square.locate(&coord!(high, 5)), Location::Boundary);
+        assert_eq!(square.locate(&coord!(i32::MAX, 5)), Location::Outside);
+        assert_eq!(square.signed_area_doubled(), 2 * 4_000_000_000_i128.pow(2));
+        assert_eq!(square.orientation(), Some(Orientation::Clockwise));
+        assert!(square.contains_rect(&coord!(low, low), &coord!(high, high)));
+        assert!(!square.contains_rect(&coord!(low, low), &coord!(i32::MAX, 0)));
+        let across = Segment::new(coord!(i32::MIN, 0), coord!(i32::MAX, 0));
+        assert!(across.crosses(&Segment::new(coord!(0, i32::MIN), coord!(0, i32::MAX))));
+    }
+
+    #[test]
+    fn test_segments() {
+        let across = Segment::new(coord!(0, 2), coord!(4, 2));
+        let down = Segment::new(coord!(2, 0), coord!(2, 4));
+        assert!(across.crosses(&down));
+        assert!(across.intersects(&down));
+        assert!(across.contains(&coord!(3, 2)));
+
+        // Touching at an end intersects but doesn't cross.
+        let touching = Segment::new(coord!(4, 2), coord!(4, 6));
+        assert!(across.intersects(&touching));
+        assert!(!across.crosses(&touching));
+
+        // Overlapping along the same line.
+        let overlap = Segment::new(coord!(3, 2), coord!(8, 2));
+        assert!(across.intersects(&overlap));
+        assert!(!across.crosses(&overlap));
+
+        let diagonal = Segment::new(coord!(0, 0), coord!(3, 3));
+        let other = Segment::new(coord!(0, 3), coord!(3, 0));
+        assert!(diagonal.crosses(&other));
+        assert!(!diagonal.is_axis_aligned());
+        let apart = Segment::new(coord!(5, 5), coord!(6, 9));
+        assert!(!diagonal.intersects(&apart));
+    }
+
+    #[test]
+    fn test_self_intersections() {
+        assert!(u_shape().is_simple());
+        assert!(u_shape().self_intersections().is_empty());
+        // A bow tie crosses in the middle.
+        let bow_tie = Polygon::new(vec![coord!(0, 0), coord!(4, 4), coord!(4, 0), coord!(0, 4)]);
+        assert_eq!(bow_tie.self_intersections(), vec![(0, 2)]);
+        // Two squares touching at a corner.
+        let touching = Polygon::new(vec![
+            coord!(0, 0),
+            coord!(2, 0),
+            coord!(2, 4),
+            coord!(4, 4),
+            coord!(4, 2),
+            coord!(0, 2),
+        ]);
+        assert!(!touching.is_simple());
+        assert_eq!(touching.self_intersections(), vec![(1, 4)]);
+        // Going back over the edge just walked, then off again.
+        let doubling_back =
+            Polygon::new(vec![coord!(0, 0), coord!(4, 0), coord!(2, 0), coord!(2, 3)]);
+        assert_eq!(doubling_back.self_intersections(), vec![(0, 1), (0, 2)]);
+        assert!(!Polygon::new(vec![coord!(0, 0), coord!(1, 1)]).is_simple());
+        assert_eq!(bow_tie.find_self_intersection(), Some((0, 2)));
+        assert_eq!(touching.find_self_intersection(), Some((1, 4)));
+        assert_eq!(doubling_back.find_self_intersection(), Some((0, 1)));
+    }
+
+    #[test]
+    fn test_find_self_intersection_against_every_pair() {
+        // Random loops along rows and columns on a small grid, so they touch
+        // and overlap themselves in lots of ways.
+        let mut seed: u64 = 12345;
+        let mut random = |below: i32| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as i32 % below
+        };
+        let mut simple = 0;
+        for _ in 0..5000 {
+            let turns = 2 + random(5);
+            let mut vertices = Vec::new();
+            let first_x = random(6);
+            let mut x = first_x;
+            for _ in 0..turns {
+                let y = random(6);
+                vertices.push(coord!(x, y));
+                x = random(6);
+                vertices.push(coord!(x, y));
+            }
+            *vertices.last_mut().unwrap() = coord!(first_x, vertices.last().unwrap().y);
+            let polygon = Polygon::new(vertices);
+            let every_pair = polygon.self_intersections();
+            match polygon.find_self_intersection() {
+                Some(pair) => assert!(every_pair.contains(&pair), "{polygon:?}"),
+                None => {
+                    assert!(every_pair.is_empty(), "{polygon:?}");
+                    simple += 1;
+                }
+            }
+        }
+        // Make sure both answers came up plenty.
+        assert!(simple > 100, "only {simple} simple loops");
+    }
+
+    #[test]
+    fn test_contains_rect() {
+        let u = u_shape();
+        assert!(u.contains_rect(&coord!(1, 1), &coord!(5, 12)));
+        assert!(u.contains_rect(&coord!(13, 9), &coord!(1, 12)));
+        // Across the gap in the U.
+        assert!(!u.contains_rect(&coord!(1, 1), &coord!(13, 12)));
+        assert!(!u.contains_rect(&coord!(1, 1), &coord!(9, 9)));
+        // Lines and points, including one along the open top of the U.
+        assert!(!u.contains_rect(&coord!(1, 1), &coord!(13, 1)));
+        assert!(u.contains_rect(&coord!(1, 9), &coord!(13, 9)));
+        assert!(u.contains_rect(&coord!(5, 1), &coord!(5, 1)));
+        assert!(!u.contains_rect(&coord!(7, 3), &coord!(7, 3)));
+    }
+
+    #[test]
+    fn test_contains_rect_against_points() {
+        // Any rectangle of whole points is in the polygon exactly when all of
+        // its points and all the halfway points between them are.
+        let u = u_shape();
+        let doubled_in = |x: i128, y: i128| u.locate_doubled(coord!(x, y)) != Location::Outside;
+        for (ax, ay) in [(1, 1), (3, 4), (5, 9), (9, 1), (13, 12)] {
+            for bx in 0..=14 {
+                for by in 0..=13 {
+                    let (a, b) = (coord!(ax, ay), coord!(bx, by));
+                    let (min, max) = bounds([&a, &b]).unwrap();
+                    let all_in = (2 * i128::from(min.x)..=2 * i128::from(max.x)).all(|x| {
+                        (2 * i128::from(min.y)..=2 * i128::from(max.y)).all(|y| doubled_in(x, y))
+                    });
+                    assert_eq!(u.contains_rect(&a, &b), all_in, "{a} {b}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,434 @@
+//! Module for saving grid frames as an animated GIF, so a simulation can be
+//! looked at after it has run. Written from scratch with just what's needed:
+//! one global palette, full size frames and LZW compression.
+
+use std::{collections::HashMap, fs, io, path::Path, time::Duration};
+
+use crate::{coord::Coord, viz::Frame};
+
+/// A color as red, green and blue.
+pub type Rgb = [u8; 3];
+
+/// Biggest code LZW is allowed to use in a GIF.
+const MAX_CODE: u16 = 4095;
+
+/// Which color each character of the grid is drawn in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+    /// Used for cells off the grid and characters with no color.
+    background: Rgb,
+    /// Used for highlighted cells.
+    highlight: Rgb,
+    colors: Vec<(char, Rgb)>,
+}
+
+impl Palette {
+    /// Most characters a palette can have, as a GIF has 256 colors and two go
+    /// to the background and highlight.
+    pub const MAX_COLORS: usize = 254;
+
+    /// Make a palette with everything in the background color and red
+    /// highlights.
+    pub fn new(background: Rgb) -> Self {
+        Self {
+            background,
+            highlight: [255, 0, 0],
+            colors: Vec::new(),
+        }
+    }
+
+    /// Draw the character in this color.
+    pub fn with(mut self, character: char, color: Rgb) -> Self {
+        match self.colors.iter_mut().find(|(c, _)| *c == character) {
+            Some(entry) => entry.1 = color,
+            None => {
+                assert!(
+                    self.colors.len() < Self::MAX_COLORS,
+                    "Palette can't have more than {} colors",
+                    Self::MAX_COLORS
+                );
+                self.colors.push((character, color));
+            }
+        }
+        self
+    }
+
+    /// Draw the character in this color, unless it already has one.
+    pub fn with_default(self, character: char, color: Rgb) -> Self {
+        if self.colors.iter().any(|(c, _)| *c == character) {
+            self
+        } else {
+            self.with(character, color)
+        }
+    }
+
+    /// Draw highlighted cells in this color.
+    pub fn with_highlight(mut self, color: Rgb) -> Self {
+        self.highlight = color;
+        self
+    }
+
+    /// Index of the color for a cell in the color table.
+    fn index(&self, character: Option<char>, highlighted: bool) -> u8 {
+        if highlighted {
+            return 1;
+        }
+        character
+            .and_then(|character| self.colors.iter().position(|(c, _)| *c == character))
+            .map_or(0, |i| i as u8 + 2)
+    }
+
+    /// The color table, padded to a power of two as GIF wants, and the number
+    /// of bits for an index into it.
+    fn table(&self) -> (Vec<u8>, u8) {
+        let used = self.colors.len() + 2;
+        let bits = (usize::BITS - (used - 1).leading_zeros()).max(1) as u8;
+        let mut table = Vec::with_capacity(3 << bits);
+        table.extend(self.background);
+        table.extend(self.highlight);
+        for (_, color) in &self.colors {
+            table.extend(color);
+        }
+        table.resize(3 << bits, 0);
+        (table, bits)
+    }
+}
+
+/// Parse a hex color like `ff8800` or `#ff8800`.
+fn parse_rgb(string: &str) -> Result<Rgb, &'static str> {
+    let hex = string.strip_prefix('#').unwrap_or(string);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err("Colors are 6 hex digits like ff8800");
+    }
+    let channel = |i: usize| {
+        u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| "Colors are 6 hex digits like ff8800")
+    };
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+impl TryFrom<&str> for Palette {
+    type Error = &'static str;
+
+    /// Parse a palette like `background=000000,highlight=ff0000,@=ffffff`.
+    /// Every other key is a single character.
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut palette = Self::new([0, 0, 0]);
+        for entry in value.split(',').filter(|entry| !entry.is_empty()) {
+            let (key, color) = entry
+                .rsplit_once('=')
+                .ok_or("Palette entries look like c=rrggbb")?;
+            let color = parse_rgb(color)?;
+            palette = match key {
+                "background" => Self {
+                    background: color,
+                    ..palette
+                },
+                "highlight" => palette.with_highlight(color),
+                _ => {
+                    let mut chars = key.chars();
+                    let (Some(character), None) = (chars.next(), chars.next()) else {
+                        return Err("Palette keys are one character, background or highlight");
+                    };
+                    if palette.colors.len() == Self::MAX_COLORS {
+                        return Err("Palette has too many colors");
+                    }
+                    palette.with(character, color)
+                }
+            };
+        }
+        Ok(palette)
+    }
+}
+
+/// Writes bits least significant first, the way GIF packs codes.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    used: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, bits: u8) {
+        self.buffer |= u32::from(code) << self.used;
+        self.used += bits;
+        while self.used >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.used -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.used > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Compress color indexes with GIF's flavor of LZW.
+fn lzw_encode(indexes: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear: u16 = 1 << min_code_size;
+    let end = clear + 1;
+    let mut writer = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut code_size = min_code_size + 1;
+    let mut next_code = end + 1;
+    writer.write(clear, code_size);
+
+    let mut indexes = indexes.iter();
+    let Some(&first) = indexes.next() else {
+        writer.write(end, code_size);
+        return writer.finish();
+    };
+    let mut current = u16::from(first);
+    for &index in indexes {
+        if let Some(&code) = table.get(&(current, index)) {
+            current = code;
+            continue;
+        }
+        writer.write(current, code_size);
+        if next_code > MAX_CODE {
+            // Table is full, so start over.
+            writer.write(clear, code_size);
+            table.clear();
+            code_size = min_code_size + 1;
+            next_code = end + 1;
+        } else {
+            table.insert((current, index), next_code);
+            next_code += 1;
+            // The decoder adds each code one step behind us, so it only
+            // needs the wider codes once it's caught up.
+            if next_code - 1 == 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+        }
+        current = u16::from(index);
+    }
+    writer.write(current, code_size);
+    // The decoder adds one more code after reading the last one.
+    if next_code == 1 << code_size && code_size < 12 {
+        code_size += 1;
+    }
+    writer.write(end, code_size);
+    writer.finish()
+}
+
+/// Turns frames of a grid into an animated GIF.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GifWriter {
+    palette: Palette,
+    /// Pixels per side of each cell.
+    scale: u16,
+    /// Time each frame stays up, in hundredths of a second.
+    delay: u16,
+}
+
+impl GifWriter {
+    /// Make a writer with 4 pixel cells and a tenth of a second per frame.
+    pub fn new(palette: Palette) -> Self {
+        Self {
+            palette,
+            scale: 4,
+            delay: 10,
+        }
+    }
+
+    /// Set how many pixels wide and tall each cell is.
+    pub fn scale(mut self, scale: u16) -> Self {
+        assert!(scale > 0, "GifWriter::scale needs to be above 0");
+        self.scale = scale;
+        self
+    }
+
+    /// Set the time each frame stays up. GIF only does hundredths of a second.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = u16::try_from(delay.as_millis() / 10).unwrap_or(u16::MAX);
+        self
+    }
+
+    /// Make the GIF. Every frame is the size of the first one's grid.
+    pub fn encode(&self, frames: &[Frame]) -> Vec<u8> {
+        let (max_x, max_y) = frames
+            .first()
+            .map_or((0, 0), |frame| (frame.grid.max_x, frame.grid.max_y));
+        let to_pixels = |cells: i32| {
+            u16::try_from(cells + 1)
+                .ok()
+                .and_then(|cells| cells.checked_mul(self.scale))
+                .expect("Grid is too big for a GIF")
+        };
+        let (width, height) = (to_pixels(max_x), to_pixels(max_y));
+        let (table, bits) = self.palette.table();
+
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend(width.to_le_bytes());
+        gif.extend(height.to_le_bytes());
+        // Global color table with 8 bit color.
+        gif.extend([0xF0 | (bits - 1), 0, 0]);
+        gif.extend(table);
+        // Loop forever.
+        gif.extend(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+        for frame in frames {
+            gif.extend([0x21, 0xF9, 0x04, 0x00]);
+            gif.extend(self.delay.to_le_bytes());
+            gif.extend([0x00, 0x00]);
+            gif.push(0x2C);
+            gif.extend([0, 0, 0, 0]);
+            gif.extend(width.to_le_bytes());
+            gif.extend(height.to_le_bytes());
+            gif.push(0x00);
+
+            let mut indexes = Vec::with_capacity(usize::from(width) * usize::from(height));
+            for y in 0..i32::from(height) {
+                for x in 0..i32::from(width) {
+                    let coord = Coord::new(x / i32::from(self.scale), y / i32::from(self.scale));
+                    indexes.push(self.palette.index(
+                        frame.grid.char_map.get(&coord).copied(),
+                        frame.highlights.contains(&coord),
+                    ));
+                }
+            }
+            // LZW needs at least 2 bits.
+            let min_code_size = bits.max(2);
+            gif.push(min_code_size);
+            for block in lzw_encode(&indexes, min_code_size).chunks(255) {
+                gif.push(block.len() as u8);
+                gif.extend(block);
+            }
+            gif.push(0x00);
+        }
+        gif.push(0x3B);
+        gif
+    }
+
+    /// Make the GIF and write it to a file.
+    pub fn save(&self, path: impl AsRef<Path>, frames: &[Frame]) -> io::Result<()> {
+        fs::write(path, self.encode(frames))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+
+    /// Undo `lzw_encode`, to check it round trips.
+    fn lzw_decode(bytes: &[u8], min_code_size: u8) -> Vec<u8> {
+        let clear: u16 = 1 << min_code_size;
+        let end = clear + 1;
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut code_size = min_code_size + 1;
+        let mut output = Vec::new();
+        let mut previous: Option<u16> = None;
+        let (mut buffer, mut used, mut position) = (0u32, 0u8, 0);
+        loop {
+            while used < code_size {
+                buffer |= u32::from(bytes[position]) << used;
+                position += 1;
+                used += 8;
+            }
+            let code = (buffer & ((1 << code_size) - 1)) as u16;
+            buffer >>= code_size;
+            used -= code_size;
+
+            if code == clear {
+                table = (0..clear).map(|i| vec![i as u8]).collect();
+                table.extend([Vec::new(), Vec::new()]);
+                code_size = min_code_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return output;
+            }
+            let entry = match (table.get(usize::from(code)), previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => {
+                    let mut entry = table[usize::from(previous)].clone();
+                    entry.push(entry[0]);
+                    entry
+                }
+                (None, None) => panic!("Bad first code"),
+            };
+            output.extend(&entry);
+            if let Some(previous) = previous {
+                let mut new = table[usize::from(previous)].clone();
+                new.push(entry[0]);
+                table.push(new);
+                if table.len() == 1 << code_size && code_size < 12 {
+                    code_size += 1;
+                }
+            }
+            previous = Some(code);
+        }
+    }
+
+    #[test]
+    fn test_lzw_round_trip() {
+        let cases: Vec<(Vec<u8>, u8)> = vec![
+            (vec![], 2),
+            (vec![1], 2),
+            (vec![0, 1, 0, 1, 0, 1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1], 2),
+            // Long and varied enough to fill the table and start over.
+            ((0..40_000u32).map(|i| (i * i / 7 % 13) as u8).collect(), 4),
+            ((0..5_000u32).map(|i| (i % 251) as u8).collect(), 8),
+        ];
+        for (indexes, min_code_size) in cases {
+            let encoded = lzw_encode(&indexes, min_code_size);
+            assert_eq!(lzw_decode(&encoded, min_code_size), indexes);
+        }
+    }
+
+    #[test]
+    fn test_palette() {
+        let palette =
+            Palette::try_from("background=#101010,@=ffffff,.=000080,highlight=00ff00").unwrap();
+        assert_eq!(palette.index(Some('@'), false), 2);
+        assert_eq!(palette.index(Some('.'), false), 3);
+        assert_eq!(palette.index(Some('?'), false), 0);
+        assert_eq!(palette.index(None, false), 0);
+        assert_eq!(palette.index(Some('@'), true), 1);
+        let (table, bits) = palette.table();
+        assert_eq!(bits, 2);
+        assert_eq!(
+            table[..12],
+            [16, 16, 16, 0, 255, 0, 255, 255, 255, 0, 0, 128]
+        );
+        // Colors already given aren't replaced by defaults.
+        let palette = palette
+            .with_default('@', [1, 2, 3])
+            .with_default('#', [4, 5, 6]);
+        assert_eq!(palette.index(Some('@'), false), 2);
+        assert_eq!(palette.index(Some('#'), false), 4);
+        assert_eq!(
+            palette.table().0[6..15],
+            [255, 255, 255, 0, 0, 128, 4, 5, 6]
+        );
+        assert!(Palette::try_from("@=fff").is_err());
+        assert!(Palette::try_from("ab=ffffff").is_err());
+        assert!(Palette::try_from("@ffffff").is_err());
+    }
+
+    #[test]
+    fn test_encode() {
+        let grid = Grid::new_from_string(&"@.\n.@".to_string());
+        let frames = vec![
+            Frame::new(grid.clone()),
+            Frame::new(grid).with_highlights([Coord::new(0, 0)]),
+        ];
+        let palette = Palette::new([0, 0, 0]).with('@', [255, 255, 255]);
+        let gif = GifWriter::new(palette)
+            .scale(3)
+            .delay(Duration::from_millis(250))
+            .encode(&frames);
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(gif[6..10], [6, 0, 6, 0]);
+        assert_eq!(gif.last(), Some(&0x3B));
+        // Two frames, each with a 25 hundredths of a second delay.
+        let delays = gif.windows(6).filter(|w| w[..4] == [0x21, 0xF9, 4, 0]);
+        assert!(delays.map(|w| w[4]).eq([25, 25]));
+    }
+}
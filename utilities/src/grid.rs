@@ -115,28 +115,30 @@ impl Grid {
         cyan(&legend)
         // legend
     }
-}
 
-impl fmt::Display for Grid {
-    /// Format the grid to print out nicely with a legend and colors.
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    /// Render the grid with a legend like `Display` does, but let the caller
+    /// decide how each cell is drawn, e.g. to color some of them.
+    pub fn render_with(&self, cell: impl Fn(&Coord, char) -> String) -> String {
         let width = self.max_y.to_string().len();
-        write!(f, "{}", self.x_legend())?;
+        let mut output = self.x_legend();
         for y in 0..=self.max_y {
-            let y_legend: String = cyan(&format!("{:>width$}{}", y, Self::Y_LEGEND_SEP));
-            write!(f, "{y_legend}")?;
+            output += &cyan(&format!("{:>width$}{}", y, Self::Y_LEGEND_SEP));
             for x in 0..=self.max_x {
-                write!(
-                    f,
-                    "{}",
-                    self.char_map.get(&Coord::new(x, y)).unwrap_or(&' ')
-                )?;
+                let coord = Coord::new(x, y);
+                output += &cell(&coord, *self.char_map.get(&coord).unwrap_or(&' '));
             }
             if y < self.max_y {
-                write!(f, "\n")?
+                output.push('\n');
             }
         }
-        Ok(())
+        output
+    }
+}
+
+impl fmt::Display for Grid {
+    /// Format the grid to print out nicely with a legend and colors.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render_with(|_, c| c.to_string()))
     }
 }
 /// Returns an Iterator over the possible Coords of the map going left->right,
@@ -200,6 +202,22 @@ mod tests {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn test_render_with() {
+        let grid = Grid::new_from_string(&"12\n34".to_string());
+        let plain = grid.to_string();
+        let marked = grid.render_with(|coord, c| {
+            if *coord == Coord::new(1, 1) {
+                "#".to_string()
+            } else {
+                c.to_string()
+            }
+        });
+        assert!(plain.ends_with("34"));
+        assert!(marked.ends_with("3#"));
+        assert_eq!(plain.replace("34", "3#"), marked);
+    }
+
     // #[test]
     // fn test_grid_display() {
     //     let string: String = "01234567890\n01234567890".to_string();
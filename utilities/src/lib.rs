@@ -1,6 +1,12 @@
 //! Various utility modules of code I will likely reuse.
 
+pub mod bigint;
 pub mod color_text;
 pub mod coord;
+pub mod geometry;
 pub use coord::Coord;
+pub mod gif;
 pub mod grid;
+pub mod parse;
+pub mod trace;
+pub mod viz;
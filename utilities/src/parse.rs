@@ -0,0 +1,516 @@
+//! Module of small parsers for the line formats puzzle inputs come in, made
+//! to be put together into bigger ones.
+//!
+//! A parser is any function taking the input and returning what it parsed
+//! along with the rest of the input, so a one off parser is just a closure.
+//! Failures remember how much input was left, which `all` turns into a line
+//! and column.
+//! ```rust
+//! use utilities::parse::{self, integers, key_value, word};
+//! let parser = key_value(word(), integers::<u32>(" "));
+//! assert_eq!(parse::all(parser, "abc: 1 2 3"), Ok(("abc", vec![1, 2, 3])));
+//! ```
+
+use std::{fmt, str::FromStr};
+
+/// Why a parser stopped.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Failure {
+    /// How many bytes of input were left where it went wrong.
+    pub remaining: usize,
+    /// What the parser was looking for.
+    pub expected: String,
+    /// The input had the right shape but a bad value, like a number too big
+    /// for its type, so trying something else instead won't help. `list` and
+    /// `opt` only back off from failures that aren't fatal.
+    pub fatal: bool,
+}
+
+impl Failure {
+    /// Fail at the start of `rest`.
+    pub fn new(rest: &str, expected: impl Into<String>) -> Self {
+        Self {
+            remaining: rest.len(),
+            expected: expected.into(),
+            fatal: false,
+        }
+    }
+
+    /// Fail at the start of `rest` without letting `list` or `opt` back off.
+    pub fn fatal(rest: &str, expected: impl Into<String>) -> Self {
+        Self {
+            fatal: true,
+            ..Self::new(rest, expected)
+        }
+    }
+
+    /// Move a failure from parsing a piece of the input to the whole of it,
+    /// where `after` bytes came after the piece.
+    fn after(mut self, after: usize) -> Self {
+        self.remaining += after;
+        self
+    }
+}
+
+/// What a parser returns: the value and the rest of the input, or why not.
+pub type Parsed<'a, T> = Result<(T, &'a str), Failure>;
+
+/// A failure placed in the input, with lines and columns counted from 1.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub expected: String,
+    /// The rest of the line where it went wrong, cut short if it's long.
+    pub found: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: expected {}, ",
+            self.line, self.column, self.expected
+        )?;
+        if self.found.is_empty() {
+            write!(f, "found the end of the line")
+        } else {
+            write!(f, "found {:?}", self.found)
+        }
+    }
+}
+
+impl ParseError {
+    /// Place the failure in the input it came from.
+    fn locate(input: &str, failure: Failure) -> Self {
+        let offset = input.len().saturating_sub(failure.remaining);
+        let (before, after) = input.split_at(offset);
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        let rest_of_line = after.lines().next().unwrap_or("");
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            expected: failure.expected,
+            found: rest_of_line.chars().take(20).collect(),
+        }
+    }
+}
+
+/// Run the parser over the whole input, allowing only blank lines after it.
+pub fn all<'a, T>(
+    parser: impl Fn(&'a str) -> Parsed<'a, T>,
+    input: &'a str,
+) -> Result<T, ParseError> {
+    let (value, rest) = parser(input).map_err(|failure| ParseError::locate(input, failure))?;
+    if rest.trim().is_empty() {
+        Ok(value)
+    } else {
+        Err(ParseError::locate(
+            input,
+            Failure::new(rest, "the end of the input"),
+        ))
+    }
+}
+
+/// Match exactly this text.
+pub fn tag<'a>(expected: &'static str) -> impl Fn(&'a str) -> Parsed<'a, &'a str> {
+    move |input: &'a str| match input.strip_prefix(expected) {
+        Some(rest) => Ok((&input[..expected.len()], rest)),
+        None => Err(Failure::new(input, format!("{expected:?}"))),
+    }
+}
+
+/// One or more characters matching the predicate, described as `what` in
+/// errors.
+pub fn take_while1<'a>(
+    predicate: impl Fn(char) -> bool,
+    what: &'static str,
+) -> impl Fn(&'a str) -> Parsed<'a, &'a str> {
+    move |input: &'a str| {
+        let end = input.find(|c| !predicate(c)).unwrap_or(input.len());
+        if end == 0 {
+            Err(Failure::new(input, what))
+        } else {
+            Ok(input.split_at(end))
+        }
+    }
+}
+
+/// One or more letters or digits.
+pub fn word<'a>() -> impl Fn(&'a str) -> Parsed<'a, &'a str> {
+    take_while1(char::is_alphanumeric, "a word")
+}
+
+/// Zero or more spaces or tabs.
+pub fn spaces<'a>() -> impl Fn(&'a str) -> Parsed<'a, &'a str> {
+    |input: &'a str| {
+        let rest = input.trim_start_matches([' ', '\t']);
+        Ok((&input[..input.len() - rest.len()], rest))
+    }
+}
+
+/// A whole number in base 10, with a `-` in front if it's negative. Fails if
+/// it doesn't fit in `T`.
+pub fn integer<'a, T: FromStr>() -> impl Fn(&'a str) -> Parsed<'a, T> {
+    |input: &'a str| {
+        let digits = input.strip_prefix('-').unwrap_or(input);
+        let end = digits
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(digits.len());
+        if end == 0 {
+            return Err(Failure::new(input, "an integer"));
+        }
+        let (number, rest) = input.split_at(input.len() - digits.len() + end);
+        match number.parse() {
+            Ok(value) => Ok((value, rest)),
+            Err(_) => Err(Failure::fatal(
+                input,
+                format!("an integer that fits in {}", std::any::type_name::<T>()),
+            )),
+        }
+    }
+}
+
+/// Change what a parser returns.
+pub fn map<'a, A, B>(
+    parser: impl Fn(&'a str) -> Parsed<'a, A>,
+    f: impl Fn(A) -> B,
+) -> impl Fn(&'a str) -> Parsed<'a, B> {
+    move |input: &'a str| parser(input).map(|(value, rest)| (f(value), rest))
+}
+
+/// Change what a parser returns with a check that can fail. A failed check
+/// is fatal, and reported at the start of what the parser matched. The error
+/// is shown as what was expected, so it should read like `"6 numbers"`.
+pub fn try_map<'a, A, B, E: fmt::Display>(
+    parser: impl Fn(&'a str) -> Parsed<'a, A>,
+    f: impl Fn(A) -> Result<B, E>,
+) -> impl Fn(&'a str) -> Parsed<'a, B> {
+    move |input: &'a str| {
+        let (value, rest) = parser(input)?;
+        match f(value) {
+            Ok(value) => Ok((value, rest)),
+            Err(error) => Err(Failure::fatal(input, error.to_string())),
+        }
+    }
+}
+
+/// Try the parser, giving None without using any input if it fails in a way
+/// that isn't fatal.
+pub fn opt<'a, T>(
+    parser: impl Fn(&'a str) -> Parsed<'a, T>,
+) -> impl Fn(&'a str) -> Parsed<'a, Option<T>> {
+    move |input: &'a str| match parser(input) {
+        Ok((value, rest)) => Ok((Some(value), rest)),
+        Err(failure) if failure.fatal => Err(failure),
+        Err(_) => Ok((None, input)),
+    }
+}
+
+/// One parser then another, keeping both results.
+pub fn pair<'a, A, B>(
+    first: impl Fn(&'a str) -> Parsed<'a, A>,
+    second: impl Fn(&'a str) -> Parsed<'a, B>,
+) -> impl Fn(&'a str) -> Parsed<'a, (A, B)> {
+    move |input: &'a str| {
+        let (a, rest) = first(input)?;
+        let (b, rest) = second(rest)?;
+        Ok(((a, b), rest))
+    }
+}
+
+/// One parser then another, keeping only the second result.
+pub fn preceded<'a, A, B>(
+    first: impl Fn(&'a str) -> Parsed<'a, A>,
+    second: impl Fn(&'a str) -> Parsed<'a, B>,
+) -> impl Fn(&'a str) -> Parsed<'a, B> {
+    map(pair(first, second), |(_, b)| b)
+}
+
+/// One parser then another, keeping only the first result.
+pub fn terminated<'a, A, B>(
+    first: impl Fn(&'a str) -> Parsed<'a, A>,
+    second: impl Fn(&'a str) -> Parsed<'a, B>,
+) -> impl Fn(&'a str) -> Parsed<'a, A> {
+    map(pair(first, second), |(a, _)| a)
+}
+
+/// Two parsers with a separator between them, keeping both results, e.g.
+/// the two ends of `3-5`.
+pub fn separated_pair<'a, A, S, B>(
+    first: impl Fn(&'a str) -> Parsed<'a, A>,
+    separator: impl Fn(&'a str) -> Parsed<'a, S>,
+    second: impl Fn(&'a str) -> Parsed<'a, B>,
+) -> impl Fn(&'a str) -> Parsed<'a, (A, B)> {
+    pair(terminated(first, separator), second)
+}
+
+/// Something between an opening and closing parser, keeping the middle.
+pub fn delimited<'a, A, T, B>(
+    open: impl Fn(&'a str) -> Parsed<'a, A>,
+    parser: impl Fn(&'a str) -> Parsed<'a, T>,
+    close: impl Fn(&'a str) -> Parsed<'a, B>,
+) -> impl Fn(&'a str) -> Parsed<'a, T> {
+    preceded(open, terminated(parser, close))
+}
+
+/// Something in brackets, starting with `open` (one of `(`, `[`, `{` or
+/// `<`) and ending with the matching close.
+pub fn bracketed<'a, T>(
+    open: char,
+    parser: impl Fn(&'a str) -> Parsed<'a, T>,
+) -> impl Fn(&'a str) -> Parsed<'a, T> {
+    let close = match open {
+        '(' => ')',
+        '[' => ']',
+        '{' => '}',
+        '<' => '>',
+        _ => panic!("bracketed: {open:?} isn't an opening bracket"),
+    };
+    move |input: &'a str| {
+        let inside = input
+            .strip_prefix(open)
+            .ok_or_else(|| Failure::new(input, format!("{open:?}")))?;
+        let (value, rest) = parser(inside)?;
+        let rest = rest
+            .strip_prefix(close)
+            .ok_or_else(|| Failure::new(rest, format!("{close:?}")))?;
+        Ok((value, rest))
+    }
+}
+
+/// One or more items with the separator between each. Stops before a
+/// separator that isn't followed by another item, unless the item after it
+/// failed in a fatal way.
+pub fn list<'a, T>(
+    item: impl Fn(&'a str) -> Parsed<'a, T>,
+    separator: &'static str,
+) -> impl Fn(&'a str) -> Parsed<'a, Vec<T>> {
+    separated_list(item, tag(separator))
+}
+
+/// `list` with a parser for the separator.
+fn separated_list<'a, T, S>(
+    item: impl Fn(&'a str) -> Parsed<'a, T>,
+    separator: impl Fn(&'a str) -> Parsed<'a, S>,
+) -> impl Fn(&'a str) -> Parsed<'a, Vec<T>> {
+    move |input: &'a str| {
+        let (first, mut rest) = item(input)?;
+        let mut items = vec![first];
+        while let Ok((_, next)) = separator(rest) {
+            match item(next) {
+                Ok((value, after)) => {
+                    items.push(value);
+                    rest = after;
+                }
+                Err(failure) if failure.fatal => return Err(failure),
+                Err(_) => break,
+            }
+        }
+        Ok((items, rest))
+    }
+}
+
+/// One or more integers with the separator between each, e.g. `1,2,3`.
+pub fn integers<'a, T: FromStr>(separator: &'static str) -> impl Fn(&'a str) -> Parsed<'a, Vec<T>> {
+    list(integer(), separator)
+}
+
+/// A key, a colon and then a value, with any spaces after the colon, e.g.
+/// `abc: def ghi`.
+pub fn key_value<'a, K, V>(
+    key: impl Fn(&'a str) -> Parsed<'a, K>,
+    value: impl Fn(&'a str) -> Parsed<'a, V>,
+) -> impl Fn(&'a str) -> Parsed<'a, (K, V)> {
+    separated_pair(key, pair(tag(":"), spaces()), value)
+}
+
+/// The end of a line, either `\n` or `\r\n`.
+pub fn line_end<'a>() -> impl Fn(&'a str) -> Parsed<'a, &'a str> {
+    |input: &'a str| {
+        let rest = input
+            .strip_prefix("\r\n")
+            .or_else(|| input.strip_prefix('\n'))
+            .ok_or_else(|| Failure::new(input, "the end of the line"))?;
+        Ok((&input[..input.len() - rest.len()], rest))
+    }
+}
+
+/// A line with nothing on it but spaces or tabs, and its line ending.
+pub fn blank_line<'a>() -> impl Fn(&'a str) -> Parsed<'a, &'a str> {
+    |input: &'a str| {
+        let (_, rest) = pair(spaces(), line_end())(input)?;
+        Ok((&input[..input.len() - rest.len()], rest))
+    }
+}
+
+/// Each line until a blank line or the end, with the parser matching the
+/// whole of each line. Takes the line endings but not the blank line, and
+/// gives an empty Vec if there are no lines.
+pub fn lines<'a, T>(
+    parser: impl Fn(&'a str) -> Parsed<'a, T>,
+) -> impl Fn(&'a str) -> Parsed<'a, Vec<T>> {
+    move |input: &'a str| {
+        let mut items = Vec::new();
+        let mut rest = input;
+        while !rest.is_empty() && blank_line()(rest).is_err() {
+            let (line, next) = rest.split_once('\n').unwrap_or((rest, ""));
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            let after = rest.len() - line.len();
+            let (value, left) = parser(line).map_err(|failure| failure.after(after))?;
+            if !left.is_empty() {
+                return Err(Failure::new(left, "the end of the line").after(after));
+            }
+            items.push(value);
+            rest = next;
+        }
+        Ok((items, rest))
+    }
+}
+
+/// One or more sections with a blank line between each. The parser should
+/// take the line ending at the end of its section, like `lines` does.
+pub fn sections<'a, T>(
+    parser: impl Fn(&'a str) -> Parsed<'a, T>,
+) -> impl Fn(&'a str) -> Parsed<'a, Vec<T>> {
+    separated_list(parser, blank_line())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(integer::<u32>()("42 rest"), Ok((42, " rest")));
+        assert_eq!(integer::<i64>()("-7,"), Ok((-7, ",")));
+        assert_eq!(
+            all(integer::<u8>(), "256").unwrap_err().expected,
+            "an integer that fits in u8"
+        );
+        assert_eq!(all(integer::<u8>(), "-1").unwrap_err().column, 1);
+        assert!(integer::<i32>()("-").is_err());
+        assert_eq!(all(integers::<u16>(","), "3,5,4,7"), Ok(vec![3, 5, 4, 7]));
+        // A separator with nothing after it is left for the next parser.
+        assert_eq!(integers::<u16>(",")("1,2,x"), Ok((vec![1, 2], ",x")));
+    }
+
+    #[test]
+    fn test_brackets() {
+        let button = bracketed('(', integers::<usize>(","));
+        assert_eq!(all(&button, "(0,2)"), Ok(vec![0, 2]));
+        let lights = bracketed('[', take_while1(|c| c == '.' || c == '#', "lights"));
+        assert_eq!(lights("[.##.] (1)"), Ok((".##.", " (1)")));
+
+        let error = all(&button, "(0,2}").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 5));
+        assert_eq!(
+            error.to_string(),
+            "line 1, column 5: expected ')', found \"}\""
+        );
+
+        let machine = separated_pair(lights, tag(" "), list(button, " "));
+        let joltage = bracketed('{', integers::<u16>(","));
+        let line = separated_pair(machine, tag(" "), joltage);
+        let ((lights, buttons), joltages) = all(line, "[.#] (3) (1,3) {3,5}").unwrap();
+        assert_eq!(lights, ".#");
+        assert_eq!(buttons, [vec![3], vec![1, 3]]);
+        assert_eq!(joltages, [3, 5]);
+    }
+
+    #[test]
+    fn test_key_value() {
+        let device = take_while1(|c: char| c.is_ascii_lowercase(), "a device name");
+        let line = key_value(&device, list(&device, " "));
+        assert_eq!(all(&line, "aaa: you hhh"), Ok(("aaa", vec!["you", "hhh"])));
+        assert_eq!(all(&line, "aaa:you"), Ok(("aaa", vec!["you"])));
+        assert_eq!(all(&line, "aaa you").unwrap_err().expected, "\":\"");
+
+        let area = separated_pair(integer::<usize>(), tag("x"), integer::<usize>());
+        let region = key_value(area, integers::<usize>(" "));
+        assert_eq!(
+            all(region, "12x5: 1 0 1 0 2 2"),
+            Ok(((12, 5), vec![1, 0, 1, 0, 2, 2]))
+        );
+    }
+
+    #[test]
+    fn test_lines_and_sections() {
+        let range = separated_pair(integer::<u64>(), tag("-"), integer::<u64>());
+        let database = separated_pair(lines(&range), tag("\n"), lines(integer::<u64>()));
+        let (ranges, ids) = all(&database, "3-5\n10-14\n\n1\n5\n").unwrap();
+        assert_eq!(ranges, [(3, 5), (10, 14)]);
+        assert_eq!(ids, [1, 5]);
+        assert_eq!(all(lines(integer::<u8>()), ""), Ok(vec![]));
+        assert_eq!(all(lines(integer::<u8>()), "1\r\n2\r\n"), Ok(vec![1, 2]));
+
+        // Errors point at the right place in the whole input.
+        let error = all(&database, "3-5\n10-14\n\n1\n5x\n").unwrap_err();
+        assert_eq!((error.line, error.column), (5, 2));
+        assert_eq!(error.expected, "the end of the line");
+        let error = all(&database, "3-5\n10_14\n").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 3));
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 3: expected \"-\", found \"_14\""
+        );
+
+        let shape = pair(
+            terminated(integer::<u8>(), tag(":\n")),
+            lines(take_while1(|c| c == '#' || c == '.', "a row of the shape")),
+        );
+        let (shapes, rest) = sections(&shape)("0:\n##\n#.\n\n1:\n.#\n\n4x4: 1 2\n").unwrap();
+        assert_eq!(shapes, [(0, vec!["##", "#."]), (1, vec![".#"])]);
+        assert_eq!(rest, "\n4x4: 1 2\n");
+
+        // Windows line endings, and blank lines with spaces on them.
+        let shape = pair(
+            terminated(integer::<u8>(), pair(tag(":"), line_end())),
+            lines(take_while1(|c| c == '#' || c == '.', "a row of the shape")),
+        );
+        let (shapes, rest) = sections(&shape)("0:\r\n##\r\n\r\n1:\r\n.#\r\n \r\n4x4").unwrap();
+        assert_eq!(shapes, [(0, vec!["##"]), (1, vec![".#"])]);
+        assert_eq!(rest, " \r\n4x4");
+        let (shapes, _) = sections(&shape)("0:\n##\n\t \n1:\n.#\n").unwrap();
+        assert_eq!(shapes.len(), 2);
+        let database = pair(
+            terminated(lines(&range), blank_line()),
+            lines(integer::<u64>()),
+        );
+        assert_eq!(
+            all(&database, "3-5\r\n10-14\r\n\r\n1\r\n5\r\n"),
+            Ok((vec![(3, 5), (10, 14)], vec![1, 5]))
+        );
+        assert_eq!(blank_line()("  \r\nx"), Ok(("  \r\n", "x")));
+        assert!(blank_line()(" x\n").is_err());
+    }
+
+    #[test]
+    fn test_try_map_and_opt() {
+        let even = try_map(integer::<u32>(), |n| {
+            if n.is_multiple_of(2) {
+                Ok(n)
+            } else {
+                Err(format!("an even number, not {n}"))
+            }
+        });
+        assert_eq!(all(&even, "4"), Ok(4));
+        let error = all(lines(&even), "4\n7\n").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+        assert_eq!(error.expected, "an even number, not 7");
+        assert_eq!(opt(tag("-"))("5"), Ok((None, "5")));
+        assert_eq!(opt(tag("-"))("-5"), Ok((Some("-"), "5")));
+        assert!(opt(&even)("3").is_err());
+        // A bad value in a list is reported where it is, rather than ending
+        // the list there.
+        let error = all(integers::<u8>(","), "1,2,300").unwrap_err();
+        assert_eq!(
+            (error.column, error.expected.as_str()),
+            (5, "an integer that fits in u8")
+        );
+        assert_eq!(
+            all(word(), "abc def").unwrap_err().expected,
+            "the end of the input"
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Leveled diagnostic output so the days can keep their debug prints without
+//! spamming full input runs. Everything is silent unless asked for with the
+//! `AOC_TRACE` environment variable (e.g. `AOC_TRACE=debug cargo run`) or a
+//! `--trace <level>` flag passed through [`init_from_args`].
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Environment variable read for the level when nothing set it first.
+pub const ENV_VAR: &str = "AOC_TRACE";
+
+/// How much diagnostic output to show. Each level includes the ones before it.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Level {
+    /// Nothing. The default.
+    Off,
+    /// Summaries, like counts of things checked.
+    Info,
+    /// Intermediate values.
+    Debug,
+    /// Every step of a hot loop.
+    Trace,
+}
+
+impl Level {
+    /// All the levels in order, indexed by their u8 value.
+    const ALL: [Level; 4] = [Level::Off, Level::Info, Level::Debug, Level::Trace];
+}
+
+impl TryFrom<&str> for Level {
+    type Error = &'static str;
+
+    /// Tries to convert a level name ("info") or number ("1") to a Level. Case
+    /// doesn't matter.
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "off" | "0" => Ok(Level::Off),
+            "info" | "1" => Ok(Level::Info),
+            "debug" | "2" => Ok(Level::Debug),
+            "trace" | "3" => Ok(Level::Trace),
+            _ => Err("Unknown trace level."),
+        }
+    }
+}
+
+/// Value stored in LEVEL before anything has set it.
+const UNSET: u8 = u8::MAX;
+
+/// Current level as a u8, or UNSET until the first lookup.
+static LEVEL: AtomicU8 = AtomicU8::new(UNSET);
+
+/// Set the level, overriding the environment variable.
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Get the current level. Reads the environment variable the first time if
+/// nothing has set the level yet. Unknown values count as Off.
+pub fn level() -> Level {
+    let mut value = LEVEL.load(Ordering::Relaxed);
+    if value == UNSET {
+        let level = std::env::var(ENV_VAR)
+            .ok()
+            .and_then(|var| Level::try_from(var.as_str()).ok())
+            .unwrap_or(Level::Off);
+        value = level as u8;
+        LEVEL.store(value, Ordering::Relaxed);
+    }
+    Level::ALL[usize::from(value)]
+}
+
+/// Check if output at this level should be shown.
+pub fn enabled(level: Level) -> bool {
+    level != Level::Off && level <= self::level()
+}
+
+/// Pull `--trace <level>`, `--trace=<level>` and `-v` flags (each `-v` is one
+/// level higher) out of the arguments and set the level from them. Returns the
+/// rest of the arguments. Panics on an unknown level. Days with no flags of
+/// their own can call this just for tracing and ignore what's returned.
+pub fn init_from_args(args: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut rest = Vec::new();
+    let mut flag_level: Option<Level> = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let value = if arg == "--trace" {
+            args.next().expect("--trace needs a level.")
+        } else if let Some(value) = arg.strip_prefix("--trace=") {
+            value.to_string()
+        } else if arg == "-v" {
+            let next = flag_level.map_or(1, |level| (level as usize + 1).min(3));
+            flag_level = Some(Level::ALL[next]);
+            continue;
+        } else {
+            rest.push(arg);
+            continue;
+        };
+        flag_level = Some(Level::try_from(value.as_str()).expect("Unknown trace level."));
+    }
+    if let Some(level) = flag_level {
+        set_level(level);
+    }
+    rest
+}
+
+/// Print to stderr if the level is enabled. The level is a [`Level`] variant name.
+/// ```rust
+/// # use utilities::trace;
+/// trace!(Debug, "checked {} combinations", 10);
+/// ```
+/// The above will expand to:
+/// ```rust
+/// if utilities::trace::enabled(utilities::trace::Level::Debug) {
+///     eprintln!("checked {} combinations", 10);
+/// }
+/// ```
+#[macro_export]
+macro_rules! trace {
+    ($level:ident, $($arg:tt)+) => {
+        if $crate::trace::enabled($crate::trace::Level::$level) {
+            eprintln!($($arg)+);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_try_from() {
+        assert_eq!(Level::try_from("off"), Ok(Level::Off));
+        assert_eq!(Level::try_from("INFO"), Ok(Level::Info));
+        assert_eq!(Level::try_from("2"), Ok(Level::Debug));
+        assert_eq!(Level::try_from(" trace\n"), Ok(Level::Trace));
+        assert!(Level::try_from("loud").is_err());
+    }
+
+    /// Only test that touches the global level, so tests running in parallel
+    /// don't fight over it.
+    #[test]
+    fn test_levels() {
+        set_level(Level::Off);
+        assert!(!enabled(Level::Off));
+        assert!(!enabled(Level::Info));
+
+        set_level(Level::Debug);
+        assert_eq!(level(), Level::Debug);
+        assert!(enabled(Level::Info));
+        assert!(enabled(Level::Debug));
+        assert!(!enabled(Level::Trace));
+
+        let args = ["-v", "file.txt", "-v"].map(String::from);
+        assert_eq!(init_from_args(args), vec!["file.txt".to_string()]);
+        assert_eq!(level(), Level::Debug);
+
+        let args = ["--trace", "trace", "a", "--trace=info", "b"].map(String::from);
+        assert_eq!(init_from_args(args), vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(level(), Level::Info);
+
+        // No flags leaves the level alone.
+        assert_eq!(init_from_args(Vec::new()), Vec::<String>::new());
+        assert_eq!(level(), Level::Info);
+        set_level(Level::Off);
+    }
+}
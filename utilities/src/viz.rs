@@ -0,0 +1,268 @@
+//! Module for watching grid simulations play out in the terminal. Each frame
+//! is drawn over the last one using ANSI cursor control.
+
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+use crate::{color_text::red, coord::Coord, grid::Grid};
+
+/// Move the cursor to the top left.
+const ANSI_HOME: &str = "\x1b[H";
+/// Clear from the cursor to the end of the screen.
+const ANSI_CLEAR_BELOW: &str = "\x1b[J";
+/// Clear the whole screen.
+const ANSI_CLEAR: &str = "\x1b[2J";
+const ANSI_HIDE_CURSOR: &str = "\x1b[?25l";
+const ANSI_SHOW_CURSOR: &str = "\x1b[?25h";
+
+/// One picture of a simulation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub grid: Grid,
+    /// Cells drawn in the highlight color.
+    pub highlights: HashSet<Coord>,
+    /// Text shown under the grid.
+    pub caption: String,
+}
+
+impl Frame {
+    /// Make a frame of the grid with nothing highlighted.
+    pub fn new(grid: Grid) -> Self {
+        Self {
+            grid,
+            highlights: HashSet::new(),
+            caption: String::new(),
+        }
+    }
+
+    /// Highlight the coordinates in this frame.
+    pub fn with_highlights(mut self, highlights: impl IntoIterator<Item = Coord>) -> Self {
+        self.highlights.extend(highlights);
+        self
+    }
+
+    /// Set the text shown under the grid.
+    pub fn with_caption(mut self, caption: &str) -> Self {
+        self.caption = caption.to_string();
+        self
+    }
+
+    /// Draw the grid with its legend, coloring the highlighted cells.
+    pub fn render(&self, highlight: fn(&str) -> String) -> String {
+        let mut output = self.grid.render_with(|coord, c| {
+            if self.highlights.contains(coord) {
+                highlight(&c.to_string())
+            } else {
+                c.to_string()
+            }
+        });
+        if !self.caption.is_empty() {
+            output.push('\n');
+            output += &self.caption;
+        }
+        output
+    }
+}
+
+/// Read lines from stdin on another thread to use as controls for an
+/// `Animator`, so playing frames never waits on input.
+pub fn stdin_controls() -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lines() {
+            let Ok(line) = line else { break };
+            if sender.send(line.trim().to_string()).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Plays frames in the terminal one after another.
+///
+/// With controls, pressing Enter while playing pauses. While paused, Enter
+/// steps one frame, `c` carries on playing and `q` quits. Without controls,
+/// frames just play with the delay between them.
+pub struct Animator<W: Write> {
+    out: W,
+    delay: Duration,
+    paused: bool,
+    highlight: fn(&str) -> String,
+    controls: Option<Receiver<String>>,
+    /// Nothing drawn yet, so the screen still needs clearing.
+    first: bool,
+}
+
+impl<W: Write> Animator<W> {
+    /// Make an animator writing to `out`, with 100ms between frames and red
+    /// highlights.
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            delay: Duration::from_millis(100),
+            paused: false,
+            highlight: red,
+            controls: None,
+            first: true,
+        }
+    }
+
+    /// Set the time each frame stays up while playing.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Start paused, so the frames are stepped through one by one.
+    pub fn paused(mut self, paused: bool) -> Self {
+        self.paused = paused;
+        self
+    }
+
+    /// Set how highlighted cells are colored, e.g. `color_text::green`.
+    pub fn highlight_with(mut self, highlight: fn(&str) -> String) -> Self {
+        self.highlight = highlight;
+        self
+    }
+
+    /// Take pause, step and quit commands from the receiver, one per line.
+    pub fn controls(mut self, controls: Receiver<String>) -> Self {
+        self.controls = Some(controls);
+        self
+    }
+
+    /// Draw the frame over the last one, then wait for the delay or, when
+    /// paused, for a command. Returns false if asked to quit.
+    pub fn show(&mut self, frame: &Frame) -> io::Result<bool> {
+        if self.first {
+            write!(self.out, "{ANSI_HIDE_CURSOR}{ANSI_CLEAR}")?;
+            self.first = false;
+        }
+        write!(
+            self.out,
+            "{ANSI_HOME}{}\n{ANSI_CLEAR_BELOW}",
+            frame.render(self.highlight)
+        )?;
+        let Some(controls) = &self.controls else {
+            self.out.flush()?;
+            thread::sleep(self.delay);
+            return Ok(true);
+        };
+
+        if !self.paused {
+            self.out.flush()?;
+            thread::sleep(self.delay);
+            match controls.try_recv() {
+                Ok(command) if command == "q" => return Ok(false),
+                Ok(_) => self.paused = true,
+                Err(TryRecvError::Empty) => return Ok(true),
+                // Nothing more can come in, so play through to the end.
+                Err(TryRecvError::Disconnected) => {
+                    self.controls = None;
+                    return Ok(true);
+                }
+            }
+        }
+        writeln!(self.out, "Paused: Enter to step, c to carry on, q to quit")?;
+        self.out.flush()?;
+        match controls.recv().as_deref() {
+            Ok("q") => Ok(false),
+            Ok("c") => {
+                self.paused = false;
+                Ok(true)
+            }
+            Ok(_) => Ok(true),
+            Err(_) => {
+                self.controls = None;
+                self.paused = false;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Show every frame in turn, stopping early if asked to quit. Returns how
+    /// many frames were shown.
+    pub fn play(&mut self, frames: impl IntoIterator<Item = Frame>) -> io::Result<usize> {
+        let mut shown = 0;
+        for frame in frames {
+            shown += 1;
+            if !self.show(&frame)? {
+                break;
+            }
+        }
+        write!(self.out, "{ANSI_SHOW_CURSOR}")?;
+        self.out.flush()?;
+        Ok(shown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color_text::green;
+
+    fn frames() -> Vec<Frame> {
+        ["ab", "cd", "ef"]
+            .into_iter()
+            .map(|s| Frame::new(Grid::new_from_string(&s.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_highlights() {
+        let frame = Frame::new(Grid::new_from_string(&"ab".to_string()))
+            .with_highlights([Coord::new(1, 0)])
+            .with_caption("round 1");
+        let rendered = frame.render(green);
+        assert!(rendered.ends_with(&format!("a{}\nround 1", green("b"))));
+    }
+
+    #[test]
+    fn test_play_without_controls() {
+        let mut out = Vec::new();
+        let mut animator = Animator::new(&mut out).delay(Duration::ZERO);
+        assert_eq!(animator.play(frames()).unwrap(), 3);
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.matches(ANSI_HOME).count(), 3);
+        assert!(out.find("ab").unwrap() < out.find("cd").unwrap());
+        assert!(out.ends_with(ANSI_SHOW_CURSOR));
+    }
+
+    #[test]
+    fn test_step_and_quit() {
+        let (sender, receiver) = mpsc::channel();
+        // Step once, then quit on the second frame.
+        sender.send(String::new()).unwrap();
+        sender.send("q".to_string()).unwrap();
+        let mut out = Vec::new();
+        let mut animator = Animator::new(&mut out)
+            .delay(Duration::ZERO)
+            .paused(true)
+            .controls(receiver);
+        assert_eq!(animator.play(frames()).unwrap(), 2);
+        let out = String::from_utf8(out).unwrap();
+        assert!(!out.contains("ef"));
+        assert_eq!(out.matches("Paused").count(), 2);
+    }
+
+    #[test]
+    fn test_pause_and_carry_on() {
+        let (sender, receiver) = mpsc::channel();
+        // Pause after the first frame, then carry on to the end.
+        sender.send(String::new()).unwrap();
+        sender.send("c".to_string()).unwrap();
+        let mut out = Vec::new();
+        let mut animator = Animator::new(&mut out)
+            .delay(Duration::ZERO)
+            .controls(receiver);
+        assert_eq!(animator.play(frames()).unwrap(), 3);
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.matches("Paused").count(), 1);
+    }
+}